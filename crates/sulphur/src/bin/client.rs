@@ -1,31 +1,291 @@
+use std::io::{BufRead, BufReader};
 use std::net::SocketAddr;
+use std::num::NonZeroU8;
+use std::path::PathBuf;
+use std::time::Duration;
 
-use clap::Parser;
-use epicentre_diagnostics::{DiagnosticLayer, Report};
-use sulphur::resource_monitor::{MeasurementType, Metrics};
-use sulphur::{CLAP_STYLE, DEFAULT_API_ADDRESS, METRICS_ENDPOINT};
+use clap::{Args, Parser, Subcommand};
+use epicentre_diagnostics::color_eyre::eyre::{self, Context, OptionExt};
+use epicentre_diagnostics::{DiagnosticLayer, Report, tracing};
+use sulphur::grading::{GradingConfig, Scale, Thresholds};
+use sulphur::graph::GraphStyle;
+use sulphur::processes::{ProcessSort, ProcessUsage, ProcessesFormat};
+use sulphur::resource_monitor::{MeasurementSelector, MeasurementType, Metrics, Resolution};
+use sulphur::server::{MetricsQuery, ProcessesQuery};
+use sulphur::status_bar::{I3BAR_PROTOCOL_HEADER, OutputFormat};
+use sulphur::{
+    CLAP_STYLE, DEFAULT_API_ADDRESS, METRICS_ENDPOINT, METRICS_STREAM_ENDPOINT,
+    PROCESSES_TOP_ENDPOINT,
+};
+
+/// How long to wait before reconnecting to the server when following.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
 
 fn main() -> Result<(), Report> {
     DiagnosticLayer.setup()?;
 
     let options = ClientOptions::parse();
-    let metrics = reqwest::blocking::get(options.metrics_http_uri())?.json::<Metrics>()?;
-    let graph = sulphur::graph::render(&metrics, options.measurement_type)?;
-    println!("{graph}");
+    if let Some(ClientCommand::Top(top_options)) = &options.command {
+        return print_top_processes(&options, top_options);
+    }
+
+    options.validate()?;
+    let grading_config = options.grading_config()?;
 
+    if !options.follow {
+        let metrics = reqwest::blocking::Client::new()
+            .get(options.metrics_http_uri())
+            .query(&options.metrics_query())
+            .send()?
+            .error_for_status()?
+            .json::<Metrics>()?;
+        let output = options.render(metrics, &grading_config)?;
+        println!("{output}");
+        return Ok(());
+    }
+
+    follow_metrics(&options, &grading_config)
+}
+
+/// Print a graph whenever the server streams new metrics, reconnecting if
+/// the stream ends, e.g. because the server restarted.
+///
+/// Only failing to connect in the first place is an error.
+fn follow_metrics(options: &ClientOptions, grading_config: &GradingConfig) -> Result<(), Report> {
+    // The default timeout of the blocking client covers reading the body as
+    // well, which would cut the (endless) stream short.
+    let client = reqwest::blocking::Client::builder().timeout(None).build()?;
+    let connect = || -> Result<reqwest::blocking::Response, Report> {
+        Ok(client
+            .get(options.metrics_stream_http_uri())
+            .query(&options.metrics_query())
+            .send()?
+            .error_for_status()?)
+    };
+    let mut response = connect()?;
+
+    if options.output_format == OutputFormat::I3bar {
+        println!("{I3BAR_PROTOCOL_HEADER}");
+    }
+
+    loop {
+        print_metrics_stream(response, options, grading_config);
+        response = loop {
+            std::thread::sleep(RECONNECT_DELAY);
+            match connect() {
+                Ok(response) => break response,
+                Err(error) => tracing::warn!(?error, "Failed to reconnect to the server"),
+            }
+        };
+    }
+}
+
+/// Print a graph for every line of the stream until it ends. Lines that
+/// can't be parsed or rendered are skipped.
+fn print_metrics_stream(
+    response: reqwest::blocking::Response,
+    options: &ClientOptions,
+    grading_config: &GradingConfig,
+) {
+    for line in BufReader::new(response).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(error) => {
+                tracing::warn!(?error, "Lost the metrics stream");
+                return;
+            }
+        };
+        let output = serde_json::from_str::<Metrics>(&line)
+            .wrap_err("Failed to parse metrics")
+            .and_then(|metrics| options.render(metrics, grading_config));
+        match (output, options.output_format) {
+            (Ok(output), OutputFormat::I3bar) => println!("[{output}],"),
+            (Ok(output), _) => println!("{output}"),
+            (Err(error), _) => tracing::warn!(?error, "Failed to render metrics"),
+        }
+    }
+    tracing::warn!("The server closed the metrics stream");
+}
+
+fn print_top_processes(options: &ClientOptions, top_options: &TopOptions) -> Result<(), Report> {
+    let query = ProcessesQuery {
+        by: top_options.by,
+        n: top_options.count,
+    };
+    let response = reqwest::blocking::Client::new()
+        .get(options.processes_top_http_uri())
+        .query(&query)
+        .send()?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        eyre::bail!("The server does not track processes, start it with --track-processes");
+    }
+
+    let processes = response.error_for_status()?.json::<Vec<ProcessUsage>>()?;
+    let output = top_options
+        .output_format
+        .format(&processes, top_options.by)?;
+    println!("{output}");
     Ok(())
 }
 
 #[derive(Parser, Debug)]
-#[command(version, author, about, styles(CLAP_STYLE))]
+#[command(
+    version,
+    author,
+    about,
+    styles(CLAP_STYLE),
+    subcommand_negates_reqs = true
+)]
 pub struct ClientOptions {
+    #[command(subcommand)]
+    pub command: Option<ClientCommand>,
+
     /// The address at which the server is configured to listen.
     #[arg(long, default_value_t = DEFAULT_API_ADDRESS)]
     pub api_address: SocketAddr,
 
     /// Graph of what metric type to render.
-    #[arg(long("type"))]
-    pub measurement_type: MeasurementType,
+    ///
+    /// Series of external commands and those pushed to the server are named
+    /// after the type, e.g. `command:queue` or `custom:builds`. Required
+    /// unless a subcommand is given.
+    #[arg(long("type"), required = true)]
+    pub measurement_type: Option<MeasurementSelector>,
+
+    /// Only show network usage of this interface.
+    ///
+    /// Affects the `net`, `net-rx` and `net-tx` graphs.
+    #[arg(long)]
+    pub interface: Option<String>,
+
+    /// Show usage of this cgroup instead of the whole system, as configured
+    /// on the server, e.g. `system.slice`.
+    ///
+    /// Affects the `cpu`, `mem`, `disk`, `disk-read` and `disk-write` graphs.
+    #[arg(long)]
+    pub cgroup: Option<String>,
+
+    /// Only show space usage of the filesystem mounted here.
+    ///
+    /// Affects the `fs` graph.
+    #[arg(long)]
+    pub mount_point: Option<String>,
+
+    /// How finely grained the history should be.
+    ///
+    /// Graphs are equally long at every resolution, so coarser ones look
+    /// further back, showing one average per minute or per hour.
+    #[arg(long, default_value_t, value_enum)]
+    pub resolution: Resolution,
+
+    /// Keep running and print a new graph whenever the server has new
+    /// measurements, instead of printing a single one.
+    ///
+    /// With `--output i3bar`, the output is a complete i3bar protocol stream.
+    /// Reconnects if the server goes away, e.g. when it restarts.
+    #[arg(short('f'), long)]
+    pub follow: bool,
+
+    /// How to present the graph, e.g. as a Waybar module.
+    #[arg(long("output"), default_value_t, value_enum)]
+    pub output_format: OutputFormat,
+
+    /// How to draw the graph.
+    ///
+    /// Note that only braille fits two measurements into a single character,
+    /// so other graphical styles are twice as wide.
+    #[arg(long, default_value_t, value_enum)]
+    pub style: GraphStyle,
+
+    /// How many rows of characters the graph should span.
+    ///
+    /// Each row adds more distinguishable levels to every measurement. Has no
+    /// effect on the `numeric` style.
+    #[arg(long, default_value_t = NonZeroU8::MIN)]
+    pub height: NonZeroU8,
+
+    /// Grade measurements against fixed thresholds, or relative to the
+    /// observed window.
+    ///
+    /// Overrides the `scale` key of the grading config.
+    #[arg(long)]
+    pub scale: Option<Scale>,
+
+    /// Which percentile of the window to auto-scale against, `100` being the
+    /// maximum.
+    ///
+    /// Overrides the `auto_scale_percentile` key of the grading config.
+    #[arg(long, value_name = "PERCENTILE")]
+    pub scale_percentile: Option<f32>,
+
+    /// Load grading thresholds from this TOML file.
+    ///
+    /// The file may contain `cpu`, `net`, `disk`, `mem`, `swap`, `fs`, `temp`,
+    /// `load` and `pressure` keys, each set to an array of three strictly
+    /// increasing thresholds, e.g. `cpu = [10, 45, 80]`. CPU, memory, swap,
+    /// filesystems and pressure are in percent, network is in Mbit/s, disk in
+    /// MB/s, temperature in °C and load per logical core.
+    #[arg(long, value_name = "PATH")]
+    pub grading_config: Option<PathBuf>,
+
+    /// CPU usage thresholds in percent, e.g. `10,45,80`.
+    #[arg(long, value_name = "LOW,MEDIUM,HIGH")]
+    pub cpu_thresholds: Option<Thresholds>,
+
+    /// Network usage thresholds in Mbit/s, e.g. `10,100,800`.
+    #[arg(long, value_name = "LOW,MEDIUM,HIGH")]
+    pub net_thresholds: Option<Thresholds>,
+
+    /// Disk throughput thresholds in MB/s, e.g. `10,100,500`.
+    #[arg(long, value_name = "LOW,MEDIUM,HIGH")]
+    pub disk_thresholds: Option<Thresholds>,
+
+    /// Memory usage thresholds in percent, e.g. `25,50,80`.
+    #[arg(long, value_name = "LOW,MEDIUM,HIGH")]
+    pub mem_thresholds: Option<Thresholds>,
+
+    /// Swap usage thresholds in percent, e.g. `25,50,80`.
+    #[arg(long, value_name = "LOW,MEDIUM,HIGH")]
+    pub swap_thresholds: Option<Thresholds>,
+
+    /// Filesystem space usage thresholds in percent, e.g. `70,85,95`.
+    #[arg(long, value_name = "LOW,MEDIUM,HIGH")]
+    pub fs_thresholds: Option<Thresholds>,
+
+    /// Temperature thresholds in degrees Celsius, e.g. `50,70,85`.
+    #[arg(long, value_name = "LOW,MEDIUM,HIGH")]
+    pub temp_thresholds: Option<Thresholds>,
+
+    /// Load average thresholds per logical core, e.g. `0.5,1,2`.
+    #[arg(long, value_name = "LOW,MEDIUM,HIGH")]
+    pub load_thresholds: Option<Thresholds>,
+
+    /// Pressure stall thresholds in percent, e.g. `5,20,50`.
+    #[arg(long, value_name = "LOW,MEDIUM,HIGH")]
+    pub pressure_thresholds: Option<Thresholds>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ClientCommand {
+    /// List the processes using the most CPU or memory right now.
+    ///
+    /// The server has to be started with `--track-processes`.
+    Top(TopOptions),
+}
+
+#[derive(Args, Debug)]
+pub struct TopOptions {
+    /// Which resource to rank processes by.
+    #[arg(long, default_value_t, value_enum)]
+    pub by: ProcessSort,
+
+    /// How many processes to list.
+    #[arg(short('n'), long, default_value_t = 5)]
+    pub count: usize,
+
+    /// How to present the processes, e.g. compactly for a tooltip.
+    #[arg(long("output"), default_value_t, value_enum)]
+    pub output_format: ProcessesFormat,
 }
 
 impl ClientOptions {
@@ -34,4 +294,224 @@ impl ClientOptions {
         let Self { api_address, .. } = self;
         format!("http://{api_address}{METRICS_ENDPOINT}")
     }
+
+    #[must_use]
+    pub fn metrics_stream_http_uri(&self) -> String {
+        let Self { api_address, .. } = self;
+        format!("http://{api_address}{METRICS_STREAM_ENDPOINT}")
+    }
+
+    #[must_use]
+    pub fn processes_top_http_uri(&self) -> String {
+        let Self { api_address, .. } = self;
+        format!("http://{api_address}{PROCESSES_TOP_ENDPOINT}")
+    }
+
+    #[must_use]
+    pub fn metrics_query(&self) -> MetricsQuery {
+        MetricsQuery {
+            resolution: self.resolution,
+            measurement_types: self
+                .measurement_type
+                .as_ref()
+                .map(|selector| vec![selector.measurement_type]),
+            samples: None,
+            recorded: true,
+        }
+    }
+
+    /// Reject filters that can't be applied to the requested graph, before
+    /// asking the server for anything.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if per-core CPU usage is requested
+    /// for a cgroup, which is only accounted as a whole.
+    pub fn validate(&self) -> Result<(), Report> {
+        let per_core_types = [MeasurementType::CpuCores, MeasurementType::CpuMax];
+        if let Some(selector) = &self.measurement_type
+            && self.cgroup.is_some()
+            && per_core_types.contains(&selector.measurement_type)
+        {
+            eyre::bail!(
+                "Per-core CPU usage is not accounted for cgroups, use `--type cpu` instead"
+            );
+        }
+        Ok(())
+    }
+
+    /// Render the requested graph from `metrics`, formatted for output.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no measurement type was given, if
+    /// the server does not report the requested series, network interface or
+    /// cgroup, or if the output can't be formatted.
+    pub fn render(
+        &self,
+        mut metrics: Metrics,
+        grading_config: &GradingConfig,
+    ) -> Result<String, Report> {
+        let selector = self
+            .measurement_type
+            .as_ref()
+            .ok_or_eyre("No measurement type to render")?;
+        self.validate()?;
+        if let Some(series) = &selector.series {
+            metrics = metrics
+                .with_named_series(selector.measurement_type, series)
+                .ok_or_eyre("The server does not report this series")?;
+        }
+        let net_types = [
+            MeasurementType::Net,
+            MeasurementType::NetRx,
+            MeasurementType::NetTx,
+        ];
+        if let Some(interface) = &self.interface
+            && net_types.contains(&selector.measurement_type)
+        {
+            metrics = metrics
+                .with_interface(interface)
+                .ok_or_eyre("The server does not report usage of this network interface")?;
+        }
+        if let Some(cgroup) = &self.cgroup
+            && MeasurementType::CGROUP_TYPES.contains(&selector.measurement_type)
+        {
+            metrics = metrics
+                .with_cgroup(cgroup)
+                .ok_or_eyre("The server does not account this cgroup")?;
+        }
+        if let Some(mount_point) = &self.mount_point
+            && selector.measurement_type == MeasurementType::Fs
+        {
+            metrics = metrics
+                .with_mount_point(mount_point)
+                .ok_or_eyre("The server does not report usage of this filesystem")?;
+        }
+
+        let graph = sulphur::graph::render(
+            &metrics,
+            selector.measurement_type,
+            grading_config,
+            self.style,
+            self.height,
+        )?;
+        let output = sulphur::status_bar::format_output(&graph, selector, self.output_format)?;
+        Ok(output)
+    }
+
+    /// Combine the grading config file (if any) with thresholds from the
+    /// command line, the latter taking precedence.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the config file can't be read or
+    /// doesn't contain a valid [`GradingConfig`].
+    pub fn grading_config(&self) -> Result<GradingConfig, Report> {
+        let mut grading_config = match &self.grading_config {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+                toml::from_str(&contents)
+                    .wrap_err_with(|| format!("Failed to parse {}", path.display()))?
+            }
+            None => GradingConfig::default(),
+        };
+
+        if let Some(scale) = self.scale {
+            grading_config.scale = scale;
+        }
+        if let Some(percentile) = self.scale_percentile {
+            grading_config.auto_scale_percentile = percentile;
+        }
+
+        let overrides = [
+            (&mut grading_config.cpu, self.cpu_thresholds),
+            (&mut grading_config.net, self.net_thresholds),
+            (&mut grading_config.disk, self.disk_thresholds),
+            (&mut grading_config.mem, self.mem_thresholds),
+            (&mut grading_config.swap, self.swap_thresholds),
+            (&mut grading_config.fs, self.fs_thresholds),
+            (&mut grading_config.temp, self.temp_thresholds),
+            (&mut grading_config.load, self.load_thresholds),
+            (&mut grading_config.pressure, self.pressure_thresholds),
+        ];
+        for (thresholds, thresholds_override) in overrides {
+            if let Some(thresholds_override) = thresholds_override {
+                *thresholds = thresholds_override;
+            }
+        }
+
+        Ok(grading_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use clap::Parser;
+    use sulphur::grading::GradingConfig;
+    use sulphur::resource_monitor::{Metrics, Series};
+
+    use super::ClientOptions;
+
+    fn render(arguments: &[&str], metrics: Metrics) -> Result<String, String> {
+        let options = ClientOptions::try_parse_from(
+            ["sulphur_client", "--style", "numeric"]
+                .iter()
+                .chain(arguments),
+        )
+        .unwrap();
+        options
+            .render(metrics, &GradingConfig::default())
+            .map_err(|error| error.to_string())
+    }
+
+    fn cpu_metrics() -> Metrics {
+        let cpu_usage = Series::cpu_usage(vec![25.]);
+        Metrics {
+            series: BTreeMap::from([(String::from("cpu"), cpu_usage)]),
+            ..Metrics::default()
+        }
+    }
+
+    #[test]
+    fn mount_point_only_affects_filesystem_graph() {
+        let output = render(&["--type", "cpu", "--mount-point", "/"], cpu_metrics());
+        assert_eq!(output.unwrap(), "25% / 25% / 25%");
+
+        let output = render(&["--type", "fs", "--mount-point", "/"], cpu_metrics());
+        assert!(output.unwrap_err().contains("filesystem"));
+    }
+
+    #[test]
+    fn cgroup_only_affects_documented_graphs() {
+        // Without a (matching) sensor there is nothing to render, not 0°C.
+        let output = render(
+            &["--type", "temp", "--cgroup", "system.slice"],
+            Metrics::default(),
+        );
+        assert_eq!(output.unwrap(), "n/a");
+
+        let output = render(
+            &["--type", "cpu", "--cgroup", "system.slice"],
+            cpu_metrics(),
+        );
+        assert!(output.unwrap_err().contains("cgroup"));
+
+        for per_core_type in ["cpu-cores", "cpu-max"] {
+            let output = render(&["--type", per_core_type, "--cgroup", "a"], cpu_metrics());
+            assert!(output.unwrap_err().contains("Per-core CPU usage"));
+        }
+    }
+
+    #[test]
+    fn interface_only_affects_network_graphs() {
+        let output = render(&["--type", "cpu", "--interface", "eth0"], cpu_metrics());
+        assert_eq!(output.unwrap(), "25% / 25% / 25%");
+
+        let output = render(&["--type", "net", "--interface", "eth0"], cpu_metrics());
+        assert!(output.unwrap_err().contains("network interface"));
+    }
 }
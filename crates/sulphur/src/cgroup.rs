@@ -0,0 +1,121 @@
+//! Resource accounting of [cgroup v2][cgroup] control groups, e.g. systemd
+//! slices and units.
+//!
+//! [cgroup]: https://docs.kernel.org/admin-guide/cgroup-v2.html
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where the unified cgroup hierarchy is mounted.
+pub const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Cumulative usage counters and the current memory usage of a cgroup.
+#[derive(Default, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct CgroupCounters {
+    /// CPU time consumed by all tasks of the cgroup.
+    pub cpu_time: Duration,
+    /// Current memory usage, unless the memory controller is disabled.
+    pub memory: Option<u64>,
+    /// The memory limit, unless there is none.
+    pub memory_limit: Option<u64>,
+    /// Data read from all block devices.
+    pub read_bytes: u64,
+    /// Data written to all block devices.
+    pub written_bytes: u64,
+}
+
+/// Resolve a configured cgroup path, e.g. `system.slice`, against
+/// [`CGROUP_ROOT`]. Absolute paths are taken as they are.
+#[must_use]
+pub fn resolve(cgroup: &Path) -> PathBuf {
+    Path::new(CGROUP_ROOT).join(cgroup)
+}
+
+/// Read the counters of the cgroup at `directory`.
+///
+/// Returns [`None`] if there is no such cgroup (anymore). Counters of disabled
+/// controllers are left empty.
+#[must_use]
+pub fn read_counters(directory: &Path) -> Option<CgroupCounters> {
+    let read = |file: &str| std::fs::read_to_string(directory.join(file)).ok();
+
+    let cpu_time = parse_cpu_stat(&read("cpu.stat")?)?;
+    let memory = read("memory.current").and_then(|contents| contents.trim().parse().ok());
+    let memory_limit = read("memory.max").and_then(|contents| parse_memory_max(&contents));
+    let (read_bytes, written_bytes) = read("io.stat")
+        .map(|contents| parse_io_stat(&contents))
+        .unwrap_or_default();
+
+    Some(CgroupCounters {
+        cpu_time,
+        memory,
+        memory_limit,
+        read_bytes,
+        written_bytes,
+    })
+}
+
+/// Parse the total CPU time out of `cpu.stat`.
+fn parse_cpu_stat(contents: &str) -> Option<Duration> {
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("usage_usec "))?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_micros)
+}
+
+/// Parse `memory.max`, which is `max` if the cgroup is not limited.
+fn parse_memory_max(contents: &str) -> Option<u64> {
+    contents.trim().parse().ok()
+}
+
+/// Sum the read and written bytes of every device in `io.stat`.
+///
+/// Each line looks like `8:0 rbytes=1 wbytes=2 rios=3 wios=4 dbytes=0 dios=0`.
+fn parse_io_stat(contents: &str) -> (u64, u64) {
+    let field = |line: &str, key: &str| {
+        line.split_whitespace()
+            .filter_map(|field| field.strip_prefix(key)?.parse::<u64>().ok())
+            .sum::<u64>()
+    };
+    contents.lines().fold((0, 0), |(read, written), line| {
+        (
+            read + field(line, "rbytes="),
+            written + field(line, "wbytes="),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::{parse_cpu_stat, parse_io_stat, parse_memory_max, resolve};
+
+    #[test]
+    fn cgroup_parsing() {
+        let cpu_stat = "usage_usec 1500000\nuser_usec 1000000\nsystem_usec 500000\n";
+        assert_eq!(parse_cpu_stat(cpu_stat), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_cpu_stat("user_usec 1\n"), None);
+
+        assert_eq!(parse_memory_max("max\n"), None);
+        assert_eq!(parse_memory_max("1073741824\n"), Some(1 << 30));
+
+        let io_stat = "8:0 rbytes=1024 wbytes=2048 rios=1 wios=2 dbytes=0 dios=0\n\
+                       259:0 rbytes=1 wbytes=2 rios=1 wios=1 dbytes=0 dios=0\n";
+        assert_eq!(parse_io_stat(io_stat), (1025, 2050));
+        assert_eq!(parse_io_stat(""), (0, 0));
+
+        assert_eq!(
+            resolve(Path::new("system.slice")),
+            Path::new("/sys/fs/cgroup/system.slice")
+        );
+        assert_eq!(
+            resolve(Path::new("/sys/fs/cgroup/user.slice")),
+            Path::new("/sys/fs/cgroup/user.slice")
+        );
+    }
+}
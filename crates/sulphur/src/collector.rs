@@ -0,0 +1,197 @@
+//! Pluggable sources of measurements, see [`Collector`].
+//!
+//! The [`ResourceMonitor`](crate::resource_monitor::ResourceMonitor) keeps a
+//! history of every series its collectors sample, and serves them as
+//! [`Series`](crate::resource_monitor::Series) in the
+//! [`Metrics`](crate::resource_monitor::Metrics).
+
+use std::fmt::Debug;
+use std::time::Duration;
+
+use sysinfo::{Networks, System};
+
+use crate::grading::{CpuUsageGrading, GradedMeasurement, NetUsageRateGrading, Thresholds};
+use crate::resource_monitor::InterfaceFilter;
+use crate::units::{CpuUsage, NetUsage, NetUsageRate};
+
+/// What collectors sample from, refreshed by the monitor right before.
+#[derive(Clone, Copy, Debug)]
+pub struct SampleContext<'a> {
+    pub system: &'a System,
+    pub networks: &'a Networks,
+    /// Time since the previous sample, zero for the very first one.
+    pub elapsed: Duration,
+}
+
+/// A source of one or more named series of measurements.
+///
+/// Series are named after the [`MeasurementType`] they are rendered as, e.g.
+/// `cpu`. Further series of the same type are qualified after a slash, e.g.
+/// `net-rx/eth0`. All series of a collector share its unit and thresholds.
+///
+/// Only CPU usage, network usage and external commands are sampled this way,
+/// the other measurements keep histories of their own units.
+///
+/// [`MeasurementType`]: crate::resource_monitor::MeasurementType
+pub trait Collector: Send + Debug {
+    /// The symbol of the unit every value is expressed in, including a leading
+    /// space if it should be separated from the value, e.g. `%` or ` Mbit/s`.
+    fn unit(&self) -> &str;
+
+    /// The thresholds to grade values against, unless a client brings its own.
+    fn default_thresholds(&self) -> Thresholds;
+
+    /// Take a new measurement of every series, by name.
+    ///
+    /// The history of a series that is left out is dropped, e.g. once a network
+    /// interface disappears.
+    fn sample(&mut self, context: &SampleContext) -> Vec<(String, f32)>;
+}
+
+/// Global CPU usage as `cpu`, and that of every logical core as `cpu-cores/0`,
+/// `cpu-cores/1` and so on.
+#[derive(Default, Debug)]
+#[must_use]
+pub struct CpuCollector;
+
+/// Combined, received and transmitted network usage of all (filtered)
+/// interfaces as `net`, `net-rx` and `net-tx`, and that of every single
+/// interface as e.g. `net-rx/eth0`.
+#[derive(Debug)]
+#[must_use]
+pub struct NetCollector {
+    interface_filter: InterfaceFilter,
+}
+
+/// How long the names of commands and custom series may be.
+pub const MAX_SERIES_NAME_LENGTH: usize = 64;
+
+/// The name of the `qualifier` series of the same type as `series`.
+#[must_use]
+pub fn qualified_series(series: &str, qualifier: &str) -> String {
+    format!("{series}/{qualifier}")
+}
+
+/// Whether `name` can be used as the qualifier of a series, and be selected
+/// as `TYPE:NAME`, i.e. consists of 1 to [`MAX_SERIES_NAME_LENGTH`] ASCII
+/// letters, digits, `-`, `_` or `.`.
+#[must_use]
+pub fn is_valid_series_name(name: &str) -> bool {
+    (1..=MAX_SERIES_NAME_LENGTH).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+impl CpuCollector {
+    pub const NAME: &str = "cpu";
+    pub const CORES: &str = "cpu-cores";
+}
+
+impl Collector for CpuCollector {
+    fn unit(&self) -> &str {
+        CpuUsage::UNIT
+    }
+
+    fn default_thresholds(&self) -> Thresholds {
+        CpuUsageGrading::THRESHOLDS
+    }
+
+    fn sample(&mut self, context: &SampleContext) -> Vec<(String, f32)> {
+        let percentage = |usage| CpuUsage::from_percentage(usage).grading_value();
+        let global = (
+            Self::NAME.to_owned(),
+            percentage(context.system.global_cpu_usage()),
+        );
+        let cores = context.system.cpus().iter().enumerate().map(|(core, cpu)| {
+            let series = qualified_series(Self::CORES, &core.to_string());
+            (series, percentage(cpu.cpu_usage()))
+        });
+        std::iter::once(global).chain(cores).collect()
+    }
+}
+
+impl NetCollector {
+    pub const NAME: &str = "net";
+    pub const RECEIVED: &str = "net-rx";
+    pub const TRANSMITTED: &str = "net-tx";
+
+    pub const fn new(interface_filter: InterfaceFilter) -> Self {
+        Self { interface_filter }
+    }
+}
+
+impl Collector for NetCollector {
+    fn unit(&self) -> &str {
+        NetUsageRate::UNIT
+    }
+
+    fn default_thresholds(&self) -> Thresholds {
+        NetUsageRateGrading::THRESHOLDS
+    }
+
+    /// Networks only know what they received since their last refresh, so
+    /// the very first sample is always idle.
+    fn sample(&mut self, context: &SampleContext) -> Vec<(String, f32)> {
+        let rate = |usage| match context.elapsed.is_zero() {
+            true => 0.,
+            false => NetUsageRate::from_usage_and_duration(usage, context.elapsed).grading_value(),
+        };
+        let directions = |received, transmitted| {
+            [
+                (Self::NAME, rate(received + transmitted)),
+                (Self::RECEIVED, rate(received)),
+                (Self::TRANSMITTED, rate(transmitted)),
+            ]
+        };
+
+        let mut samples = Vec::new();
+        let mut received_net_usage = NetUsage::from_bytes(0);
+        let mut transmitted_net_usage = NetUsage::from_bytes(0);
+        for (name, network_data) in context.networks {
+            if !self.interface_filter.matches(name) {
+                continue;
+            }
+
+            let received = NetUsage::from_bytes(network_data.received());
+            let transmitted = NetUsage::from_bytes(network_data.transmitted());
+            received_net_usage = received_net_usage + received;
+            transmitted_net_usage = transmitted_net_usage + transmitted;
+
+            samples.extend(
+                directions(received, transmitted)
+                    .map(|(series, value)| (qualified_series(series, name), value)),
+            );
+        }
+
+        samples.extend(
+            directions(received_net_usage, transmitted_net_usage)
+                .map(|(series, value)| (series.to_owned(), value)),
+        );
+        samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CpuCollector, NetCollector};
+    use crate::resource_monitor::MeasurementType;
+
+    #[test]
+    fn series_names() {
+        let names = [
+            (CpuCollector::NAME, MeasurementType::Cpu),
+            (CpuCollector::CORES, MeasurementType::CpuCores),
+            (NetCollector::NAME, MeasurementType::Net),
+            (NetCollector::RECEIVED, MeasurementType::NetRx),
+            (NetCollector::TRANSMITTED, MeasurementType::NetTx),
+        ];
+        for (series, measurement_type) in names {
+            assert_eq!(measurement_type.name(), series);
+            assert_eq!(
+                MeasurementType::of_series(&format!("{series}/qualifier")),
+                Some(measurement_type)
+            );
+        }
+    }
+}
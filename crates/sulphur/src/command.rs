@@ -0,0 +1,368 @@
+//! Site-specific numbers, e.g. a queue depth or the fan duty reported by a
+//! vendor tool, sampled by periodically running an external command.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use epicentre_diagnostics::color_eyre::eyre::{self, Context, OptionExt};
+use epicentre_diagnostics::tracing;
+use nix::errno::Errno;
+use nix::sys::signal::{Signal, killpg};
+use nix::unistd::Pid;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+
+use crate::collector::{
+    Collector, MAX_SERIES_NAME_LENGTH, SampleContext, is_valid_series_name, qualified_series,
+};
+use crate::grading::Thresholds;
+use crate::resource_monitor::MeasurementType;
+
+/// The commands to run, as loaded from the file given to `--commands`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct CommandsConfig {
+    #[serde(default, rename = "command")]
+    pub commands: Vec<CommandConfig>,
+}
+
+/// A single external command, e.g.
+///
+/// ```toml
+/// [[command]]
+/// name = "queue"
+/// command = "redis-cli llen jobs"
+/// interval_seconds = 10
+/// unit = " jobs"
+/// thresholds = [10, 100, 1000]
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct CommandConfig {
+    /// The name of the series, rendered with `--type command:NAME`.
+    pub name: String,
+    /// Run with `sh -c`, so that pipes and the like work.
+    pub command: String,
+    /// How often to run the command, which is also how long it may take.
+    #[serde(default = "CommandConfig::default_interval_seconds")]
+    pub interval_seconds: f64,
+    /// Appended to every value as is, e.g. `%` or ` jobs`.
+    #[serde(default)]
+    pub unit: String,
+    pub thresholds: Thresholds,
+}
+
+/// The newest number printed by an external command, as the series
+/// `command/NAME`.
+///
+/// The command itself is run by the [`CommandRunner`] created along with the
+/// collector, see [`command_thread`].
+#[derive(Debug)]
+#[must_use]
+pub struct CommandCollector {
+    series: String,
+    unit: String,
+    thresholds: Thresholds,
+    latest: watch::Receiver<Option<f32>>,
+}
+
+/// Runs an external command at its interval, and hands the numbers it prints
+/// to its [`CommandCollector`].
+#[derive(Debug)]
+#[must_use]
+pub struct CommandRunner {
+    name: String,
+    command: String,
+    interval: Duration,
+    latest: watch::Sender<Option<f32>>,
+}
+
+/// Read the commands configured in `path`.
+pub fn load(path: &Path) -> Result<Vec<CommandConfig>, eyre::Error> {
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+    let config: CommandsConfig = toml::from_str(&contents)
+        .wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+
+    let mut names = BTreeSet::new();
+    for command in &config.commands {
+        eyre::ensure!(
+            is_valid_series_name(&command.name),
+            "The name `{}` must consist of 1 to {MAX_SERIES_NAME_LENGTH} ASCII letters, digits, \
+             `-`, `_` or `.`",
+            command.name
+        );
+        eyre::ensure!(
+            names.insert(&command.name),
+            "There are several commands called `{}`",
+            command.name
+        );
+        command.interval()?;
+    }
+
+    Ok(config.commands)
+}
+
+/// The first word of `output` that is a (finite) number, e.g. `42` of
+/// `queue depth: 42 jobs`.
+fn parse_output(output: &str) -> Option<f32> {
+    output
+        .split_whitespace()
+        .filter_map(|word| word.parse::<f32>().ok())
+        .find(|value| value.is_finite())
+}
+
+/// Run every command at its interval until cancelled.
+///
+/// A command that fails, times out or prints no number is logged, and keeps
+/// its previous value.
+#[tracing::instrument(skip_all)]
+pub async fn command_thread(runners: Vec<CommandRunner>, cancellation_token: CancellationToken) {
+    let command_loops = async move {
+        futures_util::future::join_all(runners.into_iter().map(CommandRunner::run)).await;
+        // Without any commands, there is nothing to do until the server stops.
+        std::future::pending::<()>().await;
+    };
+
+    tokio::select! {
+        () = command_loops => {}
+        () = cancellation_token.cancelled() => {}
+    }
+}
+
+impl CommandConfig {
+    const fn default_interval_seconds() -> f64 {
+        10.
+    }
+
+    fn interval(&self) -> Result<Duration, eyre::Error> {
+        Duration::try_from_secs_f64(self.interval_seconds)
+            .ok()
+            .filter(|interval| !interval.is_zero())
+            .ok_or_else(|| eyre::eyre!("The interval of `{}` must be positive", self.name))
+    }
+}
+
+impl CommandCollector {
+    /// The type that every command series is rendered as.
+    pub const TYPE: MeasurementType = MeasurementType::Command;
+
+    /// Create the collector of a command, along with the runner that feeds it.
+    pub fn new(config: CommandConfig) -> Result<(Self, CommandRunner), eyre::Error> {
+        let interval = config.interval()?;
+        let (sender, receiver) = watch::channel(None);
+        let collector = Self {
+            series: qualified_series(&Self::TYPE.name(), &config.name),
+            unit: config.unit,
+            thresholds: config.thresholds,
+            latest: receiver,
+        };
+        let runner = CommandRunner {
+            name: config.name,
+            command: config.command,
+            interval,
+            latest: sender,
+        };
+        Ok((collector, runner))
+    }
+}
+
+impl Collector for CommandCollector {
+    fn unit(&self) -> &str {
+        &self.unit
+    }
+
+    fn default_thresholds(&self) -> Thresholds {
+        self.thresholds
+    }
+
+    /// Every refresh records the newest number, so commands that run less
+    /// often show up as steps. There is no series until the command printed
+    /// its first number.
+    fn sample(&mut self, _context: &SampleContext) -> Vec<(String, f32)> {
+        let latest = *self.latest.borrow();
+        latest
+            .map(|value| (self.series.clone(), value))
+            .into_iter()
+            .collect()
+    }
+}
+
+impl CommandRunner {
+    /// How many runs in a row may fail before the last good value is dropped.
+    pub const MAX_FAILURES: u32 = 3;
+
+    async fn run(self) {
+        let mut interval = tokio::time::interval(self.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut failures = 0;
+        loop {
+            interval.tick().await;
+            let sample = self.sample().await;
+            self.record(sample, &mut failures);
+        }
+    }
+
+    /// Hand a successful sample to the collector, or count a failed one.
+    ///
+    /// The last good value stays in place for a few failed runs, so that a
+    /// single hiccup doesn't leave a gap, but after
+    /// [`MAX_FAILURES`](Self::MAX_FAILURES) in a row the series stops being
+    /// recorded until the command succeeds again.
+    fn record(&self, sample: Result<f32, eyre::Error>, failures: &mut u32) {
+        match sample {
+            Ok(value) => {
+                *failures = 0;
+                self.latest.send_replace(Some(value));
+            }
+            Err(error) => {
+                *failures += 1;
+                tracing::warn!(name = self.name, ?error, "Failed to sample a command");
+                if *failures == Self::MAX_FAILURES {
+                    tracing::warn!(
+                        name = self.name,
+                        failures,
+                        "Dropping the last value of a failing command"
+                    );
+                    self.latest.send_replace(None);
+                }
+            }
+        }
+    }
+
+    /// Run the command once, killing it if it takes longer than its interval.
+    ///
+    /// The command runs in its own process group, so that killing it also
+    /// kills everything it started, e.g. the stages of a pipeline.
+    async fn sample(&self) -> Result<f32, eyre::Error> {
+        let child = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .kill_on_drop(true)
+            .spawn()
+            .wrap_err("Failed to run the command")?;
+        // Also kills the command if the server stops while it runs.
+        let mut process_group = ProcessGroup(child.id());
+        let output = tokio::time::timeout(self.interval, child.wait_with_output())
+            .await
+            .wrap_err("The command timed out")?
+            .wrap_err("Failed to run the command")?;
+        process_group.0 = None;
+        eyre::ensure!(
+            output.status.success(),
+            "The command exited with {}",
+            output.status
+        );
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        parse_output(&stdout).ok_or_eyre("The command printed no number")
+    }
+}
+
+/// The process group of a running command, which is killed when dropped
+/// unless the command finished.
+#[derive(Debug)]
+struct ProcessGroup(Option<u32>);
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        let Some(process_group) = self.0 else {
+            return;
+        };
+        let Ok(pid) = i32::try_from(process_group) else {
+            return;
+        };
+        match killpg(Pid::from_raw(pid), Signal::SIGKILL) {
+            // The command may have exited on its own in the meantime.
+            Ok(()) | Err(Errno::ESRCH) => {}
+            Err(error) => tracing::warn!(process_group, ?error, "Failed to kill a command"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use epicentre_diagnostics::color_eyre::eyre;
+    use tokio::sync::watch;
+
+    use super::{CommandRunner, CommandsConfig, load, parse_output};
+
+    #[test]
+    fn command_parsing() {
+        assert_eq!(parse_output("42\n"), Some(42.));
+        assert_eq!(parse_output("queue depth: 12.5 jobs"), Some(12.5));
+        assert_eq!(parse_output("NaN 7"), Some(7.));
+        assert_eq!(parse_output("no jobs\n"), None);
+
+        let config: CommandsConfig = toml::from_str(
+            r#"
+            [[command]]
+            name = "queue"
+            command = "redis-cli llen jobs"
+            thresholds = [10, 100, 1000]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.commands[0].name, "queue");
+        assert_eq!(
+            config.commands[0].interval().unwrap(),
+            Duration::from_secs(10)
+        );
+        assert!(config.commands[0].unit.is_empty());
+
+        let mut command = config.commands[0].clone();
+        command.interval_seconds = 0.;
+        assert!(command.interval().is_err());
+
+        let path =
+            std::env::temp_dir().join(format!("sulphur-commands-{}.toml", std::process::id()));
+        for (name, valid) in [
+            ("queue", true),
+            ("redis/queue", false),
+            ("redis:queue", false),
+        ] {
+            std::fs::write(
+                &path,
+                format!(
+                    "[[command]]\nname = \"{name}\"\ncommand = \"true\"\nthresholds = [1, 2, 3]\n"
+                ),
+            )
+            .unwrap();
+            assert_eq!(load(&path).is_ok(), valid, "{name}");
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failing_command() {
+        let (sender, receiver) = watch::channel(None);
+        let runner = CommandRunner {
+            name: "queue".to_owned(),
+            command: "false".to_owned(),
+            interval: Duration::from_secs(1),
+            latest: sender,
+        };
+        let mut failures = 0;
+
+        runner.record(Ok(1.), &mut failures);
+        for _ in 1..CommandRunner::MAX_FAILURES {
+            runner.record(Err(eyre::eyre!("exited with 1")), &mut failures);
+            assert_eq!(*receiver.borrow(), Some(1.));
+        }
+        runner.record(Err(eyre::eyre!("exited with 1")), &mut failures);
+        assert_eq!(*receiver.borrow(), None);
+
+        runner.record(Ok(2.), &mut failures);
+        assert_eq!((*receiver.borrow(), failures), (Some(2.), 0));
+    }
+}
@@ -1,10 +1,28 @@
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 use uom::si;
 
-use crate::units::{CpuUsage, NetUsageRate};
+use crate::units::{
+    CpuUsage, DiskIoRate, FsUsage, LoadAverage, MemUsage, NetUsageRate, Pressure, Temperature,
+};
+
+/// How "busy" a measurement is, as a fractional level from `0` to `1`.
+///
+/// The level is continuous, so that graphs may render it at whatever
+/// resolution they have available. [`MeasurementGrade::band`] reduces it to
+/// one of four coarse [`GradeBand`]s.
+#[derive(PartialEq, PartialOrd, Clone, Copy, Debug)]
+#[must_use]
+pub struct MeasurementGrade(f32);
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
-#[repr(usize)]
-pub enum MeasurementGrade {
+/// Coarse classification of a [`MeasurementGrade`].
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+#[must_use]
+pub enum GradeBand {
     Idle,
     Low,
     Medium,
@@ -16,20 +34,426 @@ pub trait MeasurementGrading {
     fn scale(&self, measurement: Self::Measurement) -> MeasurementGrade;
 }
 
+/// A measurement that can be graded against [`Thresholds`].
+pub trait GradedMeasurement: Copy {
+    /// The symbol of the unit that [`GradedMeasurement::grading_value`] is
+    /// expressed in.
+    const UNIT: &'static str;
+
+    /// The value to compare against the thresholds, in the unit the thresholds
+    /// of this measurement are expressed in.
+    fn grading_value(&self) -> f32;
+}
+
+/// Boundaries between adjacent [`MeasurementGrade`]s.
+///
+/// A measurement below `low` falls into [`GradeBand::Idle`], one at or above
+/// `high` into [`GradeBand::High`], and so on. Within each band, the grade is
+/// interpolated linearly. The unit is defined by the [`GradedMeasurement`]
+/// being graded.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[serde(try_from = "[f32; 3]", into = "[f32; 3]")]
+pub struct Thresholds {
+    low: f32,
+    medium: f32,
+    high: f32,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ThresholdsError {
+    #[error("Thresholds must be finite numbers")]
+    NotFinite,
+    #[error("Thresholds must be strictly increasing")]
+    NotMonotonic,
+    #[error("Expected exactly three comma-separated thresholds")]
+    WrongCount,
+    #[error("Failed to parse a threshold")]
+    MalformedNumber(#[from] std::num::ParseFloatError),
+}
+
+/// Data-driven grading of any [`GradedMeasurement`].
+#[derive(Clone, Copy, Debug)]
+#[must_use]
+pub struct ThresholdGrading<M> {
+    thresholds: Thresholds,
+    measurement: PhantomData<fn(M)>,
+}
+
+/// Grading relative to the observed window of measurements.
+///
+/// Rather than using absolute thresholds, measurements are compared against a
+/// reference value - the maximum or some percentile of the window - so that the
+/// graph shows relative activity even when absolute levels are low.
+#[derive(Clone, Copy, Debug)]
+#[must_use]
+pub struct AutoScaleGrading<M> {
+    reference: f32,
+    measurement: PhantomData<fn(M)>,
+}
+
+/// How measurements are mapped to [`MeasurementGrade`]s.
+#[derive(ValueEnum, Serialize, Deserialize, Default, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Scale {
+    /// Use the absolute [`Thresholds`] of each measurement.
+    #[default]
+    Fixed,
+    /// Scale relative to the observed window, see [`AutoScaleGrading`].
+    Auto,
+}
+
+/// Per-measurement [`Thresholds`], e.g. as loaded from a configuration file.
+///
+/// CPU, memory, swap, filesystem and pressure thresholds are in percent,
+/// network thresholds are in megabits per second, disk thresholds in megabytes
+/// per second, temperature thresholds in degrees Celsius and load thresholds
+/// per logical core.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct GradingConfig {
+    pub scale: Scale,
+    /// The percentile of the window to auto-scale against, `100` being the
+    /// maximum. Only used with [`Scale::Auto`].
+    pub auto_scale_percentile: f32,
+    pub cpu: Thresholds,
+    pub net: Thresholds,
+    pub disk: Thresholds,
+    pub mem: Thresholds,
+    pub swap: Thresholds,
+    pub fs: Thresholds,
+    pub temp: Thresholds,
+    pub load: Thresholds,
+    pub pressure: Thresholds,
+}
+
 pub struct CpuUsageGrading;
 pub struct NetUsageRateGrading;
+pub struct DiskIoRateGrading;
+pub struct MemUsageGrading;
+pub struct FsUsageGrading;
+pub struct TemperatureGrading;
+pub struct LoadAverageGrading;
+pub struct PressureGrading;
+
+impl MeasurementGrade {
+    pub const IDLE: Self = Self(0.);
+    pub const HIGH: Self = Self(1.);
+
+    /// Compensates for rounding errors at band boundaries, e.g. `1/3 * 3`.
+    const QUANTIZATION_EPSILON: f32 = 1e-4;
+
+    /// Create a grade from a level, clamping it to `0..=1`.
+    ///
+    /// A NaN level is considered idle.
+    pub const fn new(level: f32) -> Self {
+        match level.is_nan() {
+            true => Self::IDLE,
+            false => Self(level.clamp(0., 1.)),
+        }
+    }
+
+    #[must_use]
+    pub const fn level(self) -> f32 {
+        self.0
+    }
+
+    /// Reduce the grade to one of `steps` discrete steps, `0..steps`.
+    ///
+    /// Only a grade of exactly `1` reaches the topmost step.
+    #[expect(clippy::cast_precision_loss)]
+    #[expect(clippy::cast_possible_truncation)]
+    #[expect(clippy::cast_sign_loss)]
+    #[must_use]
+    pub fn quantize(self, steps: usize) -> usize {
+        let top_step = steps.saturating_sub(1);
+        let step = self
+            .0
+            .mul_add(top_step as f32, Self::QUANTIZATION_EPSILON)
+            .floor() as usize;
+        step.min(top_step)
+    }
+
+    pub fn band(self) -> GradeBand {
+        match self.quantize(4) {
+            0 => GradeBand::Idle,
+            1 => GradeBand::Low,
+            2 => GradeBand::Medium,
+            _ => GradeBand::High,
+        }
+    }
+}
+
+impl Thresholds {
+    pub fn new(low: f32, medium: f32, high: f32) -> Result<Self, ThresholdsError> {
+        if ![low, medium, high].iter().all(|t| t.is_finite()) {
+            return Err(ThresholdsError::NotFinite);
+        }
+
+        if !(low < medium && medium < high) {
+            return Err(ThresholdsError::NotMonotonic);
+        }
+
+        Ok(Self { low, medium, high })
+    }
+
+    const fn new_unchecked(low: f32, medium: f32, high: f32) -> Self {
+        Self { low, medium, high }
+    }
+
+    pub fn grade(&self, value: f32) -> MeasurementGrade {
+        let Self { low, medium, high } = *self;
+        let (band, band_start, band_end) = if value >= high {
+            return MeasurementGrade::HIGH;
+        } else if value >= medium {
+            (2., medium, high)
+        } else if value >= low {
+            (1., low, medium)
+        } else if value > 0. && low > 0. {
+            (0., 0., low)
+        } else {
+            return MeasurementGrade::IDLE;
+        };
+
+        let within_band = (value - band_start) / (band_end - band_start);
+        MeasurementGrade::new((band + within_band) / 3.)
+    }
+}
+
+impl TryFrom<[f32; 3]> for Thresholds {
+    type Error = ThresholdsError;
+
+    fn try_from([low, medium, high]: [f32; 3]) -> Result<Self, Self::Error> {
+        Self::new(low, medium, high)
+    }
+}
+
+impl From<Thresholds> for [f32; 3] {
+    fn from(thresholds: Thresholds) -> Self {
+        [thresholds.low, thresholds.medium, thresholds.high]
+    }
+}
+
+impl FromStr for Thresholds {
+    type Err = ThresholdsError;
+
+    /// Parse thresholds from a `low,medium,high` string, e.g. `10,45,80`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let thresholds: Vec<f32> = s
+            .split(',')
+            .map(|t| t.trim().parse())
+            .collect::<Result<_, _>>()?;
+        let thresholds: [f32; 3] = thresholds
+            .try_into()
+            .map_err(|_| ThresholdsError::WrongCount)?;
+        Self::try_from(thresholds)
+    }
+}
+
+impl<M> ThresholdGrading<M> {
+    pub const fn new(thresholds: Thresholds) -> Self {
+        Self {
+            thresholds,
+            measurement: PhantomData,
+        }
+    }
+}
+
+impl<M: GradedMeasurement> MeasurementGrading for ThresholdGrading<M> {
+    type Measurement = M;
+
+    fn scale(&self, measurement: Self::Measurement) -> MeasurementGrade {
+        self.thresholds.grade(measurement.grading_value())
+    }
+}
+
+impl<M: GradedMeasurement> AutoScaleGrading<M> {
+    /// Fractions of the reference value at which grades change.
+    const RELATIVE_THRESHOLDS: Thresholds = Thresholds::new_unchecked(0.25, 0.5, 0.75);
+
+    /// Build a grading that scales against the given percentile of `window`.
+    ///
+    /// The percentile is clamped to `0..=100`, with `100` meaning the maximum.
+    pub fn from_window(window: &[M], percentile: f32) -> Self {
+        let values = sorted_grading_values(window);
+        Self {
+            reference: percentile_of_sorted(&values, percentile).unwrap_or(0.),
+            measurement: PhantomData,
+        }
+    }
+}
+
+/// Collect the finite grading values of a series, in ascending order.
+pub fn sorted_grading_values<M: GradedMeasurement>(series: &[M]) -> Vec<f32> {
+    let mut values: Vec<f32> = series
+        .iter()
+        .map(GradedMeasurement::grading_value)
+        .filter(|value| value.is_finite())
+        .collect();
+    values.sort_by(f32::total_cmp);
+    values
+}
+
+/// Pick the given percentile (clamped to `0..=100`) of ascending `values`,
+/// using the nearest rank.
+#[expect(clippy::cast_precision_loss)]
+#[expect(clippy::cast_possible_truncation)]
+#[expect(clippy::cast_sign_loss)]
+#[must_use]
+pub fn percentile_of_sorted(values: &[f32], percentile: f32) -> Option<f32> {
+    let last_rank = values.len().checked_sub(1)?;
+    let quantile = percentile.clamp(0., 100.) / 100.;
+    let rank = (quantile * last_rank as f32).round() as usize;
+    values.get(rank).copied()
+}
+
+impl<M: GradedMeasurement> MeasurementGrading for AutoScaleGrading<M> {
+    type Measurement = M;
+
+    fn scale(&self, measurement: Self::Measurement) -> MeasurementGrade {
+        if self.reference <= 0. {
+            return MeasurementGrade::IDLE;
+        }
+
+        let fraction = measurement.grading_value() / self.reference;
+        Self::RELATIVE_THRESHOLDS.grade(fraction)
+    }
+}
+
+impl Default for GradingConfig {
+    fn default() -> Self {
+        Self {
+            scale: Scale::default(),
+            auto_scale_percentile: 100.,
+            cpu: CpuUsageGrading::THRESHOLDS,
+            net: NetUsageRateGrading::THRESHOLDS,
+            disk: DiskIoRateGrading::THRESHOLDS,
+            mem: MemUsageGrading::THRESHOLDS,
+            swap: MemUsageGrading::THRESHOLDS,
+            fs: FsUsageGrading::THRESHOLDS,
+            temp: TemperatureGrading::THRESHOLDS,
+            load: LoadAverageGrading::THRESHOLDS,
+            pressure: PressureGrading::THRESHOLDS,
+        }
+    }
+}
+
+/// Plain values, e.g. of collector series, are already in the unit of their
+/// thresholds. The unit itself is only known to the collector.
+impl GradedMeasurement for f32 {
+    const UNIT: &'static str = "";
+
+    fn grading_value(&self) -> f32 {
+        *self
+    }
+}
+
+impl GradedMeasurement for CpuUsage {
+    const UNIT: &'static str = "%";
+
+    fn grading_value(&self) -> f32 {
+        self.get::<si::ratio::percent>()
+    }
+}
+
+impl GradedMeasurement for NetUsageRate {
+    const UNIT: &'static str = " Mbit/s";
+
+    fn grading_value(&self) -> f32 {
+        self.as_information_rate()
+            .get::<si::information_rate::megabit_per_second>()
+    }
+}
+
+impl GradedMeasurement for DiskIoRate {
+    const UNIT: &'static str = " MB/s";
+
+    fn grading_value(&self) -> f32 {
+        self.as_information_rate()
+            .get::<si::information_rate::megabyte_per_second>()
+    }
+}
+
+impl GradedMeasurement for MemUsage {
+    const UNIT: &'static str = "%";
+
+    fn grading_value(&self) -> f32 {
+        self.as_ratio().get::<si::ratio::percent>()
+    }
+}
+
+impl GradedMeasurement for FsUsage {
+    const UNIT: &'static str = "%";
+
+    fn grading_value(&self) -> f32 {
+        self.as_ratio().get::<si::ratio::percent>()
+    }
+}
+
+impl GradedMeasurement for Temperature {
+    const UNIT: &'static str = "°C";
+
+    fn grading_value(&self) -> f32 {
+        self.get::<si::thermodynamic_temperature::degree_celsius>()
+    }
+}
+
+/// Graded by the one-minute load, the most responsive of the three.
+impl GradedMeasurement for LoadAverage {
+    const UNIT: &'static str = "";
+
+    fn grading_value(&self) -> f32 {
+        self.one()
+    }
+}
+
+/// Graded by the share of time in which at least some tasks were stalled.
+impl GradedMeasurement for Pressure {
+    const UNIT: &'static str = "%";
+
+    fn grading_value(&self) -> f32 {
+        self.some().get::<si::ratio::percent>()
+    }
+}
+
+impl CpuUsageGrading {
+    pub const THRESHOLDS: Thresholds = Thresholds::new_unchecked(10., 45., 80.);
+}
+
+impl NetUsageRateGrading {
+    pub const THRESHOLDS: Thresholds = Thresholds::new_unchecked(10., 100., 800.);
+}
+
+impl DiskIoRateGrading {
+    pub const THRESHOLDS: Thresholds = Thresholds::new_unchecked(10., 100., 500.);
+}
+
+impl MemUsageGrading {
+    pub const THRESHOLDS: Thresholds = Thresholds::new_unchecked(25., 50., 80.);
+}
+
+impl FsUsageGrading {
+    /// Filesystems only become a problem when they are almost full.
+    pub const THRESHOLDS: Thresholds = Thresholds::new_unchecked(70., 85., 95.);
+}
+
+impl TemperatureGrading {
+    pub const THRESHOLDS: Thresholds = Thresholds::new_unchecked(50., 70., 85.);
+}
+
+impl LoadAverageGrading {
+    /// A load above one per core means tasks are queueing up.
+    pub const THRESHOLDS: Thresholds = Thresholds::new_unchecked(0.5, 1., 2.);
+}
+
+impl PressureGrading {
+    pub const THRESHOLDS: Thresholds = Thresholds::new_unchecked(5., 20., 50.);
+}
 
 impl MeasurementGrading for CpuUsageGrading {
     type Measurement = CpuUsage;
 
     fn scale(&self, measurement: Self::Measurement) -> MeasurementGrade {
-        match measurement.get::<si::ratio::percent>() {
-            ..10. => MeasurementGrade::Idle,
-            10.0..45. => MeasurementGrade::Low,
-            45.0..80. => MeasurementGrade::Medium,
-            80.0.. => MeasurementGrade::High,
-            _ => unreachable!(),
-        }
+        Self::THRESHOLDS.grade(measurement.grading_value())
     }
 }
 
@@ -37,16 +461,55 @@ impl MeasurementGrading for NetUsageRateGrading {
     type Measurement = NetUsageRate;
 
     fn scale(&self, measurement: Self::Measurement) -> MeasurementGrade {
-        match measurement
-            .as_information_rate()
-            .get::<si::information_rate::megabit_per_second>()
-        {
-            ..10. => MeasurementGrade::Idle,
-            10.0..100. => MeasurementGrade::Low,
-            100.0..800. => MeasurementGrade::Medium,
-            800.0.. => MeasurementGrade::High,
-            _ => unreachable!(),
-        }
+        Self::THRESHOLDS.grade(measurement.grading_value())
+    }
+}
+
+impl MeasurementGrading for DiskIoRateGrading {
+    type Measurement = DiskIoRate;
+
+    fn scale(&self, measurement: Self::Measurement) -> MeasurementGrade {
+        Self::THRESHOLDS.grade(measurement.grading_value())
+    }
+}
+
+impl MeasurementGrading for MemUsageGrading {
+    type Measurement = MemUsage;
+
+    fn scale(&self, measurement: Self::Measurement) -> MeasurementGrade {
+        Self::THRESHOLDS.grade(measurement.grading_value())
+    }
+}
+
+impl MeasurementGrading for FsUsageGrading {
+    type Measurement = FsUsage;
+
+    fn scale(&self, measurement: Self::Measurement) -> MeasurementGrade {
+        Self::THRESHOLDS.grade(measurement.grading_value())
+    }
+}
+
+impl MeasurementGrading for TemperatureGrading {
+    type Measurement = Temperature;
+
+    fn scale(&self, measurement: Self::Measurement) -> MeasurementGrade {
+        Self::THRESHOLDS.grade(measurement.grading_value())
+    }
+}
+
+impl MeasurementGrading for LoadAverageGrading {
+    type Measurement = LoadAverage;
+
+    fn scale(&self, measurement: Self::Measurement) -> MeasurementGrade {
+        Self::THRESHOLDS.grade(measurement.grading_value())
+    }
+}
+
+impl MeasurementGrading for PressureGrading {
+    type Measurement = Pressure;
+
+    fn scale(&self, measurement: Self::Measurement) -> MeasurementGrade {
+        Self::THRESHOLDS.grade(measurement.grading_value())
     }
 }
 
@@ -56,26 +519,52 @@ mod tests {
 
     use uom::si;
 
-    use super::{CpuUsageGrading, MeasurementGrade, MeasurementGrading, NetUsageRateGrading};
-    use crate::units::{CpuUsage, NetUsage, NetUsageRate};
+    use super::{
+        AutoScaleGrading, CpuUsageGrading, DiskIoRateGrading, FsUsageGrading, GradeBand,
+        GradingConfig, MeasurementGrade, MeasurementGrading, MemUsageGrading, NetUsageRateGrading,
+        TemperatureGrading, ThresholdGrading, Thresholds, ThresholdsError,
+    };
+    use crate::units::{
+        CpuUsage, DiskIo, DiskIoRate, FsUsage, MemUsage, NetUsage, NetUsageRate, Temperature,
+    };
 
     #[test]
     fn cpu_usage_grading() {
         assert_eq!(
-            CpuUsageGrading.scale(CpuUsage::from_percentage(1.)),
-            MeasurementGrade::Idle
+            CpuUsageGrading.scale(CpuUsage::from_percentage(1.)).band(),
+            GradeBand::Idle
         );
         assert_eq!(
-            CpuUsageGrading.scale(CpuUsage::from_percentage(20.)),
-            MeasurementGrade::Low
+            CpuUsageGrading.scale(CpuUsage::from_percentage(20.)).band(),
+            GradeBand::Low
         );
         assert_eq!(
-            CpuUsageGrading.scale(CpuUsage::from_percentage(60.)),
-            MeasurementGrade::Medium
+            CpuUsageGrading.scale(CpuUsage::from_percentage(60.)).band(),
+            GradeBand::Medium
         );
         assert_eq!(
-            CpuUsageGrading.scale(CpuUsage::from_percentage(90.)),
-            MeasurementGrade::High
+            CpuUsageGrading.scale(CpuUsage::from_percentage(90.)).band(),
+            GradeBand::High
+        );
+    }
+
+    #[test]
+    fn disk_io_rate_grading() {
+        let bands = [1, 50, 200, 1000].map(|megabytes| {
+            let disk_io = DiskIo::from(si::u64::Information::new::<si::information::megabyte>(
+                megabytes,
+            ));
+            let rate = DiskIoRate::from_io_and_duration(disk_io, Duration::from_secs(1));
+            DiskIoRateGrading.scale(rate).band()
+        });
+        assert_eq!(
+            bands,
+            [
+                GradeBand::Idle,
+                GradeBand::Low,
+                GradeBand::Medium,
+                GradeBand::High
+            ]
         );
     }
 
@@ -83,32 +572,224 @@ mod tests {
     fn net_usage_rate_grading() {
         let grader = NetUsageRateGrading;
         assert_eq!(
-            grader.scale(NetUsageRate::from_usage_and_duration(
-                NetUsage::from(si::u64::Information::new::<si::information::kilobyte>(1)),
-                Duration::from_secs(1)
-            )),
-            MeasurementGrade::Idle
+            grader
+                .scale(NetUsageRate::from_usage_and_duration(
+                    NetUsage::from(si::u64::Information::new::<si::information::kilobyte>(1)),
+                    Duration::from_secs(1)
+                ))
+                .band(),
+            GradeBand::Idle
         );
         assert_eq!(
-            grader.scale(NetUsageRate::from_usage_and_duration(
-                NetUsage::from(si::u64::Information::new::<si::information::megabyte>(2)),
-                Duration::from_secs(1)
-            )),
-            MeasurementGrade::Low
+            grader
+                .scale(NetUsageRate::from_usage_and_duration(
+                    NetUsage::from(si::u64::Information::new::<si::information::megabyte>(2)),
+                    Duration::from_secs(1)
+                ))
+                .band(),
+            GradeBand::Low
         );
         assert_eq!(
-            grader.scale(NetUsageRate::from_usage_and_duration(
-                NetUsage::from(si::u64::Information::new::<si::information::megabyte>(20)),
-                Duration::from_secs(1)
-            )),
-            MeasurementGrade::Medium
+            grader
+                .scale(NetUsageRate::from_usage_and_duration(
+                    NetUsage::from(si::u64::Information::new::<si::information::megabyte>(20)),
+                    Duration::from_secs(1)
+                ))
+                .band(),
+            GradeBand::Medium
         );
         assert_eq!(
-            grader.scale(NetUsageRate::from_usage_and_duration(
-                NetUsage::from(si::u64::Information::new::<si::information::megabit>(950)),
-                Duration::from_secs(1)
-            )),
-            MeasurementGrade::High
+            grader
+                .scale(NetUsageRate::from_usage_and_duration(
+                    NetUsage::from(si::u64::Information::new::<si::information::megabit>(950)),
+                    Duration::from_secs(1)
+                ))
+                .band(),
+            GradeBand::High
         );
     }
+
+    #[test]
+    fn fs_usage_grading() {
+        let bands = [50, 80, 90, 99]
+            .map(|used| FsUsageGrading.scale(FsUsage::from_bytes(used, 100)).band());
+        assert_eq!(
+            bands,
+            [
+                GradeBand::Idle,
+                GradeBand::Low,
+                GradeBand::Medium,
+                GradeBand::High
+            ]
+        );
+    }
+
+    #[test]
+    fn temperature_grading() {
+        let bands = [35., 60., 80., 95.].map(|degrees| {
+            TemperatureGrading
+                .scale(Temperature::from_celsius(degrees))
+                .band()
+        });
+        assert_eq!(
+            bands,
+            [
+                GradeBand::Idle,
+                GradeBand::Low,
+                GradeBand::Medium,
+                GradeBand::High
+            ]
+        );
+    }
+
+    #[test]
+    fn mem_usage_grading() {
+        let gib = 1024 * 1024 * 1024;
+        assert_eq!(
+            MemUsageGrading
+                .scale(MemUsage::from_bytes(gib, 16 * gib))
+                .band(),
+            GradeBand::Idle
+        );
+        assert_eq!(
+            MemUsageGrading
+                .scale(MemUsage::from_bytes(6 * gib, 16 * gib))
+                .band(),
+            GradeBand::Low
+        );
+        assert_eq!(
+            MemUsageGrading
+                .scale(MemUsage::from_bytes(10 * gib, 16 * gib))
+                .band(),
+            GradeBand::Medium
+        );
+        assert_eq!(
+            MemUsageGrading
+                .scale(MemUsage::from_bytes(15 * gib, 16 * gib))
+                .band(),
+            GradeBand::High
+        );
+        assert_eq!(
+            MemUsageGrading.scale(MemUsage::unused()).band(),
+            GradeBand::Idle
+        );
+    }
+
+    #[test]
+    fn thresholds_validation() {
+        assert!(Thresholds::new(10., 45., 80.).is_ok());
+        assert!(matches!(
+            Thresholds::new(10., 10., 80.),
+            Err(ThresholdsError::NotMonotonic)
+        ));
+        assert!(matches!(
+            Thresholds::new(80., 45., 10.),
+            Err(ThresholdsError::NotMonotonic)
+        ));
+        assert!(matches!(
+            Thresholds::new(10., f32::NAN, 80.),
+            Err(ThresholdsError::NotFinite)
+        ));
+    }
+
+    #[test]
+    fn thresholds_parsing() {
+        assert_eq!(
+            "1, 2.5,3".parse::<Thresholds>().unwrap(),
+            Thresholds::new(1., 2.5, 3.).unwrap()
+        );
+        assert!(matches!(
+            "1,2".parse::<Thresholds>(),
+            Err(ThresholdsError::WrongCount)
+        ));
+        assert!(matches!(
+            "1,two,3".parse::<Thresholds>(),
+            Err(ThresholdsError::MalformedNumber(_))
+        ));
+        assert!(matches!(
+            "3,2,1".parse::<Thresholds>(),
+            Err(ThresholdsError::NotMonotonic)
+        ));
+    }
+
+    #[test]
+    fn threshold_grading() {
+        let grader = ThresholdGrading::<CpuUsage>::new(Thresholds::new(1., 2., 3.).unwrap());
+        assert_eq!(
+            grader.scale(CpuUsage::from_percentage(0.5)).band(),
+            GradeBand::Idle
+        );
+        assert_eq!(
+            grader.scale(CpuUsage::from_percentage(1.)).band(),
+            GradeBand::Low
+        );
+        assert_eq!(
+            grader.scale(CpuUsage::from_percentage(2.5)).band(),
+            GradeBand::Medium
+        );
+        assert_eq!(
+            grader.scale(CpuUsage::from_percentage(30.)).band(),
+            GradeBand::High
+        );
+    }
+
+    #[test]
+    fn grading_config_parsing() {
+        let config: GradingConfig = toml::from_str("net = [100, 1000, 8000]").unwrap();
+        assert_eq!(config.net, Thresholds::new(100., 1000., 8000.).unwrap());
+        assert_eq!(config.cpu, CpuUsageGrading::THRESHOLDS);
+
+        assert!(toml::from_str::<GradingConfig>("cpu = [80, 45, 10]").is_err());
+        assert!(toml::from_str::<GradingConfig>("gpu = [10, 45, 80]").is_err());
+    }
+
+    #[test]
+    fn auto_scale_grading() {
+        let window = [0., 1., 2., 3., 4.].map(CpuUsage::from_percentage);
+
+        let grader = AutoScaleGrading::from_window(&window, 100.);
+        assert_eq!(
+            window.map(|m| grader.scale(m).band()),
+            [
+                GradeBand::Idle,
+                GradeBand::Low,
+                GradeBand::Medium,
+                GradeBand::High,
+                GradeBand::High,
+            ]
+        );
+
+        let grader = AutoScaleGrading::from_window(&window, 50.);
+        assert_eq!(
+            grader.scale(CpuUsage::from_percentage(1.)).band(),
+            GradeBand::Medium
+        );
+        assert_eq!(
+            grader.scale(CpuUsage::from_percentage(2.)).band(),
+            GradeBand::High
+        );
+
+        let idle_window = [CpuUsage::from_percentage(0.); 4];
+        let grader = AutoScaleGrading::from_window(&idle_window, 100.);
+        assert_eq!(
+            grader.scale(CpuUsage::from_percentage(0.)).band(),
+            GradeBand::Idle
+        );
+    }
+
+    #[test]
+    fn measurement_grade_quantization() {
+        let thresholds = Thresholds::new(10., 20., 30.).unwrap();
+        assert_eq!(thresholds.grade(10.).band(), GradeBand::Low);
+        assert_eq!(thresholds.grade(20.).band(), GradeBand::Medium);
+        assert_eq!(thresholds.grade(15.).quantize(7), 3);
+        assert_eq!(thresholds.grade(30.), MeasurementGrade::HIGH);
+        assert_eq!(MeasurementGrade::new(f32::NAN), MeasurementGrade::IDLE);
+
+        for steps in [4, 8, 12, 16] {
+            assert_eq!(MeasurementGrade::IDLE.quantize(steps), 0);
+            assert_eq!(MeasurementGrade::HIGH.quantize(steps), steps - 1);
+            assert_eq!(MeasurementGrade::new(0.99).quantize(steps), steps - 2);
+        }
+    }
 }
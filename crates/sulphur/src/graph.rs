@@ -1,42 +1,482 @@
+use std::collections::BTreeMap;
 use std::fmt::Write as _;
+use std::num::NonZeroU8;
 
-use crate::grading::{CpuUsageGrading, MeasurementGrading, NetUsageRateGrading};
-use crate::resource_monitor::{MeasurementType, Metrics};
+use clap::ValueEnum;
+
+use crate::collector::{CpuCollector, NetCollector};
+use crate::grading::{
+    AutoScaleGrading, GradedMeasurement, GradingConfig, MeasurementGrade, MeasurementGrading,
+    Scale, ThresholdGrading, Thresholds,
+};
+use crate::resource_monitor::{MeasurementType, Metrics, Series};
+use crate::summary::SeriesSummary;
+use crate::units::FsUsage;
 
 pub const GRAPH_DENSITY: u8 = 2;
 
-pub const GRAPH_SIGILS: [[char; 4]; 4] = [
-    ['⣀', '⣄', '⣆', '⣇'],
-    ['⣠', '⣤', '⣦', '⣧'],
-    ['⣰', '⣴', '⣶', '⣷'],
-    ['⣸', '⣼', '⣾', '⣿'],
+/// How many dots a single braille character stacks vertically.
+pub const GRAPH_SIGIL_HEIGHT: u8 = 4;
+
+/// Braille characters, indexed by the amount of dots in their right (newer)
+/// and left (older) columns.
+pub const GRAPH_SIGILS: [[char; 5]; 5] = [
+    ['⠀', '⡀', '⡄', '⡆', '⡇'],
+    ['⢀', '⣀', '⣄', '⣆', '⣇'],
+    ['⢠', '⣠', '⣤', '⣦', '⣧'],
+    ['⢰', '⣰', '⣴', '⣶', '⣷'],
+    ['⢸', '⣸', '⣼', '⣾', '⣿'],
 ];
 
+/// Block elements, indexed by how much of the character they fill.
+pub const BLOCK_SIGILS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// ASCII characters, indexed by how much of the character they "fill".
+pub const ASCII_SIGILS: [char; 5] = [' ', '_', '-', '=', '#'];
+
+/// Rendered instead of a graph and its summary when nothing was measured.
+pub const NOT_AVAILABLE: &str = "n/a";
+
+#[derive(ValueEnum, Default, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GraphStyle {
+    /// Braille characters, two measurements per character.
+    #[default]
+    Braille,
+    /// Unicode block elements, one measurement per character.
+    Blocks,
+    /// Plain ASCII, one measurement per character.
+    Ascii,
+    /// The current, average and peak values as text.
+    Numeric,
+}
+
+/// A rendered measurement, along with what status bars need to know about it.
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct Graph {
+    /// The graph itself, in the requested [`GraphStyle`].
+    pub text: String,
+    /// The current, average and peak values, see [`render_numeric`].
+    pub summary: String,
+    /// The grade of the newest measurement.
+    pub latest_grade: MeasurementGrade,
+}
+
+/// Renders a series of measurements in a particular [`GraphStyle`].
+#[derive(Clone, Copy, Debug)]
+struct SeriesRenderer<'a> {
+    grading_config: &'a GradingConfig,
+    style: GraphStyle,
+    height: NonZeroU8,
+    /// How many measurements graphs span, see [`Metrics::graph_samples`].
+    graph_samples: usize,
+}
+
 pub fn render(
     metrics: &Metrics,
     measurement_type: MeasurementType,
-) -> Result<String, std::fmt::Error> {
-    let measurement_grades: Vec<_> = match measurement_type {
-        MeasurementType::Cpu => metrics
-            .cpu_usage
-            .iter()
-            .map(|m| CpuUsageGrading.scale(*m))
-            .collect(),
-        MeasurementType::Net => metrics
-            .net_usage_rate
+    grading_config: &GradingConfig,
+    style: GraphStyle,
+    height: NonZeroU8,
+) -> Result<Graph, std::fmt::Error> {
+    let renderer = SeriesRenderer {
+        grading_config,
+        style,
+        height,
+        graph_samples: metrics.graph_samples,
+    };
+
+    let series = |name| metrics.series.get(name);
+
+    match measurement_type {
+        MeasurementType::Cpu => {
+            renderer.render_series(series(CpuCollector::NAME), grading_config.cpu)
+        }
+        MeasurementType::CpuCores => metrics
+            .cpu_core_usage()
+            .map(|core_usage| renderer.render_series(Some(core_usage), grading_config.cpu))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Graph::stack),
+        MeasurementType::CpuMax => {
+            renderer.render_series(metrics.max_cpu_core_usage().as_ref(), grading_config.cpu)
+        }
+        MeasurementType::Net => {
+            renderer.render_series(series(NetCollector::NAME), grading_config.net)
+        }
+        MeasurementType::NetRx => {
+            renderer.render_series(series(NetCollector::RECEIVED), grading_config.net)
+        }
+        MeasurementType::NetTx => {
+            renderer.render_series(series(NetCollector::TRANSMITTED), grading_config.net)
+        }
+        MeasurementType::Disk => renderer.render(&metrics.disk.disk_io_rate, grading_config.disk),
+        MeasurementType::DiskRead => {
+            renderer.render(&metrics.disk.disk_read_rate, grading_config.disk)
+        }
+        MeasurementType::DiskWrite => {
+            renderer.render(&metrics.disk.disk_write_rate, grading_config.disk)
+        }
+        MeasurementType::Mem => renderer.render(&metrics.mem_usage, grading_config.mem),
+        MeasurementType::Swap => renderer.render(&metrics.swap_usage, grading_config.swap),
+        MeasurementType::Fs => render_filesystems(&metrics.filesystems, grading_config.fs),
+        MeasurementType::Temp => renderer.render(&metrics.temperature, grading_config.temp),
+        MeasurementType::Load => renderer.render(&metrics.load_average, grading_config.load),
+        MeasurementType::CpuPressure => {
+            renderer.render(&metrics.cpu_pressure, grading_config.pressure)
+        }
+        MeasurementType::MemPressure => {
+            renderer.render(&metrics.memory_pressure, grading_config.pressure)
+        }
+        MeasurementType::IoPressure => {
+            renderer.render(&metrics.io_pressure, grading_config.pressure)
+        }
+        MeasurementType::Command | MeasurementType::Custom => {
+            renderer.render_own_series(series(&measurement_type.name()))
+        }
+    }
+}
+
+/// Render the space usage of every filesystem as text, one per line.
+///
+/// Filesystems are always graded against their fixed thresholds, since there
+/// is no window to auto-scale against.
+pub fn render_filesystems(
+    filesystems: &BTreeMap<String, FsUsage>,
+    thresholds: Thresholds,
+) -> Result<Graph, std::fmt::Error> {
+    let grading = ThresholdGrading::new(thresholds);
+    filesystems
+        .iter()
+        .map(|(mount_point, usage)| {
+            let mut text = format!("{mount_point} ");
+            write_value(&mut text, usage.grading_value(), FsUsage::UNIT)?;
+            Ok(Graph {
+                summary: text.clone(),
+                text,
+                latest_grade: grading.scale(*usage),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Graph::stack)
+}
+
+impl Graph {
+    /// Stack several graphs on top of each other, e.g. one per CPU core.
+    ///
+    /// The latest grade of the stack is that of its busiest graph.
+    fn stack(graphs: Vec<Self>) -> Self {
+        let latest_grade = graphs
             .iter()
-            .map(|m| NetUsageRateGrading.scale(*m))
-            .collect(),
+            .map(|graph| graph.latest_grade)
+            .max_by(|a, b| a.level().total_cmp(&b.level()))
+            .unwrap_or(MeasurementGrade::IDLE);
+        let (text, summary): (Vec<_>, Vec<_>) = graphs
+            .into_iter()
+            .map(|graph| (graph.text, graph.summary))
+            .unzip();
+
+        Self {
+            text: text.join("\n"),
+            summary: summary.join("\n"),
+            latest_grade,
+        }
+    }
+}
+
+impl SeriesRenderer<'_> {
+    fn render<M: GradedMeasurement>(
+        &self,
+        series: &[M],
+        thresholds: Thresholds,
+    ) -> Result<Graph, std::fmt::Error> {
+        self.render_with_unit(series, M::UNIT, thresholds)
+    }
+
+    /// Render a collector series, which brings its own unit. A missing series
+    /// is rendered like an empty one.
+    fn render_series(
+        &self,
+        series: Option<&Series>,
+        thresholds: Thresholds,
+    ) -> Result<Graph, std::fmt::Error> {
+        let Some(series) = series else {
+            return self.render::<f32>(&[], thresholds);
+        };
+        self.render_with_unit(&series.values, &series.unit, thresholds)
+    }
+
+    /// Render a series against the thresholds it brings along, e.g. those
+    /// configured for an external command.
+    fn render_own_series(&self, series: Option<&Series>) -> Result<Graph, std::fmt::Error> {
+        series.map_or_else(
+            || Ok(Graph::stack(Vec::new())),
+            |series| self.render_series(Some(series), series.thresholds),
+        )
+    }
+
+    fn render_with_unit<M: GradedMeasurement>(
+        &self,
+        series: &[M],
+        unit: &str,
+        thresholds: Thresholds,
+    ) -> Result<Graph, std::fmt::Error> {
+        // Nothing was measured, e.g. there is no such sensor, which would
+        // otherwise look like an idle graph.
+        if series.is_empty() {
+            return Ok(Graph {
+                text: String::from(NOT_AVAILABLE),
+                summary: String::from(NOT_AVAILABLE),
+                latest_grade: MeasurementGrade::IDLE,
+            });
+        }
+
+        // Only the graph is padded, since idle measurements that were never
+        // taken would skew the summary.
+        let mut grades = self.grade(series, thresholds);
+        if grades.len() < self.graph_samples {
+            grades.resize(self.graph_samples, MeasurementGrade::IDLE);
+        }
+        let summary = render_numeric_with_unit(series, unit)?;
+        let text = match self.style {
+            GraphStyle::Braille => render_grades(&grades, self.height)?,
+            GraphStyle::Blocks => render_column_grades(&grades, &BLOCK_SIGILS, self.height)?,
+            GraphStyle::Ascii => render_column_grades(&grades, &ASCII_SIGILS, self.height)?,
+            GraphStyle::Numeric => summary.clone(),
+        };
+
+        Ok(Graph {
+            text,
+            summary,
+            latest_grade: grades.first().copied().unwrap_or(MeasurementGrade::IDLE),
+        })
+    }
+
+    fn grade<M: GradedMeasurement>(
+        &self,
+        series: &[M],
+        thresholds: Thresholds,
+    ) -> Vec<MeasurementGrade> {
+        match self.grading_config.scale {
+            Scale::Fixed => grade_with(series, &ThresholdGrading::new(thresholds)),
+            Scale::Auto => grade_with(
+                series,
+                &AutoScaleGrading::from_window(series, self.grading_config.auto_scale_percentile),
+            ),
+        }
+    }
+}
+
+fn grade_with<M, G>(series: &[M], grading: &G) -> Vec<MeasurementGrade>
+where
+    M: Copy,
+    G: MeasurementGrading<Measurement = M>,
+{
+    series.iter().map(|m| grading.scale(*m)).collect()
+}
+
+/// Render braille characters, two measurements per character.
+///
+/// The graph is `height` characters tall, which gives each measurement
+/// `height * GRAPH_SIGIL_HEIGHT` distinct levels. Rows are separated by
+/// newlines, the topmost row coming first.
+pub fn render_grades(
+    measurement_grades: &[MeasurementGrade],
+    height: NonZeroU8,
+) -> Result<String, std::fmt::Error> {
+    let sigil_height = usize::from(GRAPH_SIGIL_HEIGHT);
+    let height = usize::from(height.get());
+
+    // Every measurement gets at least one dot, so that idle graphs are visible.
+    let dots: Vec<usize> = measurement_grades
+        .iter()
+        .map(|grade| 1 + grade.quantize(sigil_height * height))
+        .collect();
+
+    let graph_length = dots.len() / 2;
+    let mut rows = Vec::with_capacity(height);
+    for row in (0..height).rev() {
+        let dots_in_row = |dots: usize| dots.saturating_sub(row * sigil_height).min(sigil_height);
+        let mut row_buffer = String::with_capacity(graph_length);
+        for sigil_index in 0..graph_length {
+            let dots_0 = dots_in_row(dots[sigil_index * 2]);
+            let dots_1 = dots_in_row(dots[sigil_index * 2 + 1]);
+            let sigil = GRAPH_SIGILS[dots_0][dots_1];
+            write!(&mut row_buffer, "{sigil}",)?;
+        }
+        rows.push(row_buffer.chars().rev().collect::<String>());
+    }
+
+    Ok(rows.join("\n"))
+}
+
+/// Render one character per measurement, using `sigils` ordered from empty to
+/// full.
+///
+/// Like [`render_grades`], the graph is `height` characters tall, and rows are
+/// separated by newlines, the topmost row coming first.
+pub fn render_column_grades(
+    measurement_grades: &[MeasurementGrade],
+    sigils: &[char],
+    height: NonZeroU8,
+) -> Result<String, std::fmt::Error> {
+    let sigil_height = sigils.len() - 1;
+    let height = usize::from(height.get());
+
+    // Every measurement gets at least one level, so that idle graphs are visible.
+    let levels: Vec<usize> = measurement_grades
+        .iter()
+        .map(|grade| 1 + grade.quantize(sigil_height * height))
+        .collect();
+
+    let mut rows = Vec::with_capacity(height);
+    for row in (0..height).rev() {
+        let mut row_buffer = String::with_capacity(levels.len());
+        for level in levels.iter().rev() {
+            let level_in_row = level.saturating_sub(row * sigil_height).min(sigil_height);
+            write!(&mut row_buffer, "{}", sigils[level_in_row])?;
+        }
+        rows.push(row_buffer);
+    }
+
+    Ok(rows.join("\n"))
+}
+
+/// Render the current (newest), average and peak value of a series, or
+/// [`NOT_AVAILABLE`] if it is empty.
+pub fn render_numeric<M: GradedMeasurement>(series: &[M]) -> Result<String, std::fmt::Error> {
+    render_numeric_with_unit(series, M::UNIT)
+}
+
+fn render_numeric_with_unit<M: GradedMeasurement>(
+    series: &[M],
+    unit: &str,
+) -> Result<String, std::fmt::Error> {
+    let Some(summary) = SeriesSummary::new(series) else {
+        return Ok(String::from(NOT_AVAILABLE));
     };
 
-    let graph_length = measurement_grades.len() / 2;
-    let mut graph_buffer = String::with_capacity(graph_length);
-    for sigil_index in 0..graph_length {
-        let index_0 = measurement_grades[sigil_index * 2];
-        let index_1 = measurement_grades[sigil_index * 2 + 1];
-        let sigil = GRAPH_SIGILS[index_0 as usize][index_1 as usize];
-        write!(&mut graph_buffer, "{sigil}",)?;
+    let mut buffer = String::new();
+    for (index, value) in [summary.latest, summary.mean, summary.max]
+        .into_iter()
+        .enumerate()
+    {
+        if index > 0 {
+            write!(&mut buffer, " / ")?;
+        }
+        write_value(&mut buffer, value, unit)?;
     }
 
-    Ok(graph_buffer.chars().rev().collect())
+    Ok(buffer)
+}
+
+/// Write a value with one decimal place if it is small, or none otherwise.
+fn write_value(buffer: &mut String, value: f32, unit: &str) -> std::fmt::Result {
+    match value.abs() < 10. {
+        true => write!(buffer, "{value:.1}{unit}"),
+        false => write!(buffer, "{value:.0}{unit}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::num::NonZeroU8;
+
+    use super::{
+        ASCII_SIGILS, BLOCK_SIGILS, GraphStyle, render, render_column_grades, render_filesystems,
+        render_grades, render_numeric,
+    };
+    use crate::grading::{FsUsageGrading, GradeBand, GradingConfig, MeasurementGrade};
+    use crate::resource_monitor::{MeasurementType, Metrics, Series};
+    use crate::units::{CpuUsage, FsUsage};
+
+    #[test]
+    fn single_row_graph() {
+        let grades = [
+            MeasurementGrade::HIGH,
+            MeasurementGrade::IDLE,
+            MeasurementGrade::new(0.5),
+            MeasurementGrade::IDLE,
+        ];
+        let graph = render_grades(&grades, NonZeroU8::MIN).unwrap();
+        assert_eq!(graph, "⣠⣸");
+    }
+
+    #[test]
+    fn stacked_graph() {
+        let grades = [
+            MeasurementGrade::HIGH,
+            MeasurementGrade::new(0.5),
+            MeasurementGrade::IDLE,
+            MeasurementGrade::IDLE,
+        ];
+        let graph = render_grades(&grades, NonZeroU8::new(2).unwrap()).unwrap();
+        assert_eq!(graph, "⠀⢸\n⣀⣿");
+    }
+
+    #[test]
+    fn column_graphs() {
+        let grades = [
+            MeasurementGrade::HIGH,
+            MeasurementGrade::new(0.5),
+            MeasurementGrade::IDLE,
+        ];
+        let graph = render_column_grades(&grades, &BLOCK_SIGILS, NonZeroU8::MIN).unwrap();
+        assert_eq!(graph, "▁▄█");
+
+        let graph = render_column_grades(&grades, &ASCII_SIGILS, NonZeroU8::new(2).unwrap());
+        assert_eq!(graph.unwrap(), "  #\n_##");
+    }
+
+    #[test]
+    fn numeric_graph() {
+        let series = [4., 1., 100., 15.].map(CpuUsage::from_percentage);
+        let text = render_numeric(&series).unwrap();
+        assert_eq!(text, "4.0% / 30% / 100%");
+    }
+
+    #[test]
+    fn padded_graph() {
+        let cpu_usage = Series::cpu_usage(vec![100.]);
+        let metrics = Metrics {
+            series: BTreeMap::from([(String::from("cpu"), cpu_usage)]),
+            graph_samples: 4,
+            ..Metrics::default()
+        };
+        let graph = render(
+            &metrics,
+            MeasurementType::Cpu,
+            &GradingConfig::default(),
+            GraphStyle::Ascii,
+            NonZeroU8::MIN,
+        )
+        .unwrap();
+        assert_eq!(graph.text, "___#");
+        assert_eq!(graph.summary, "100% / 100% / 100%");
+
+        let graph = render(
+            &metrics,
+            MeasurementType::Temp,
+            &GradingConfig::default(),
+            GraphStyle::Ascii,
+            NonZeroU8::MIN,
+        )
+        .unwrap();
+        assert_eq!(
+            (graph.text.as_str(), graph.summary.as_str()),
+            ("n/a", "n/a")
+        );
+        assert_eq!(graph.latest_grade, MeasurementGrade::IDLE);
+    }
+
+    #[test]
+    fn filesystem_graph() {
+        let filesystems = BTreeMap::from([
+            (String::from("/"), FsUsage::from_bytes(40, 100)),
+            (String::from("/nix"), FsUsage::from_bytes(97, 100)),
+        ]);
+        let graph = render_filesystems(&filesystems, FsUsageGrading::THRESHOLDS).unwrap();
+        assert_eq!(graph.text, "/ 40%\n/nix 97%");
+        assert_eq!(graph.latest_grade.band(), GradeBand::High);
+    }
 }
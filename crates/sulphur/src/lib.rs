@@ -1,10 +1,19 @@
 #![deny(clippy::allow_attributes)]
 #![expect(clippy::missing_errors_doc)]
 
+pub mod cgroup;
+pub mod collector;
+pub mod command;
 pub mod grading;
 pub mod graph;
+pub mod persistence;
+pub mod pressure;
+pub mod processes;
+pub mod prometheus;
 pub mod resource_monitor;
 pub mod server;
+pub mod status_bar;
+pub mod summary;
 pub mod units;
 
 use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
@@ -24,3 +33,26 @@ pub const DEFAULT_API_ADDRESS: SocketAddr =
     SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 8899));
 
 pub const METRICS_ENDPOINT: &str = "/metrics";
+
+/// Streams [`resource_monitor::Metrics`] as newline-delimited JSON, one line
+/// per refresh of the server.
+pub const METRICS_STREAM_ENDPOINT: &str = "/metrics/stream";
+
+/// Records a [`resource_monitor::CustomMeasurement`] of the series `{name}`,
+/// see [`server::custom_metrics_endpoint`].
+pub const METRICS_CUSTOM_ENDPOINT: &str = "/metrics/custom/{name}";
+
+/// Exposes a [`summary::SeriesSummary`] of every series.
+pub const METRICS_SUMMARY_ENDPOINT: &str = "/metrics/summary";
+
+/// Exposes the newest measurements in the Prometheus text format.
+pub const PROMETHEUS_ENDPOINT: &str = "/metrics/prometheus";
+
+/// Pushes the newest measurements of subscribed
+/// [`resource_monitor::MeasurementType`]s over a WebSocket, see
+/// [`server::metrics_websocket_endpoint`].
+pub const METRICS_WEBSOCKET_ENDPOINT: &str = "/metrics/ws";
+
+/// Lists the processes using the most CPU or memory, see
+/// [`server::processes_top_endpoint`].
+pub const PROCESSES_TOP_ENDPOINT: &str = "/processes/top";
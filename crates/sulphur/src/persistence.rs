@@ -0,0 +1,102 @@
+//! Keeping the history of the [`ResourceMonitor`] across restarts.
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use epicentre_diagnostics::color_eyre::eyre::{self, Context};
+use epicentre_diagnostics::tracing;
+use tokio::sync::Mutex as AsyncMutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::resource_monitor::{HistorySnapshot, ResourceMonitor};
+
+/// Where the history is kept by default, following the XDG base directory
+/// specification: `$XDG_STATE_HOME/sulphur/history.json`, falling back to
+/// `~/.local/state/sulphur/history.json`.
+///
+/// Returns [`None`] if neither variable is set to an absolute path.
+#[must_use]
+pub fn default_history_path() -> Option<PathBuf> {
+    let absolute = |variable: &str| {
+        std::env::var_os(variable)
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+    };
+    let state_home = absolute("XDG_STATE_HOME")
+        .or_else(|| absolute("HOME").map(|home| home.join(".local").join("state")))?;
+    Some(state_home.join("sulphur").join("history.json"))
+}
+
+/// Read a snapshot written by [`save`], or [`None`] if there is none yet.
+pub fn load(path: &Path) -> Result<Option<HistorySnapshot>, eyre::Error> {
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => {
+            return Err(error).wrap_err_with(|| format!("Failed to read {}", path.display()));
+        }
+    };
+    serde_json::from_slice(&contents)
+        .map(Some)
+        .wrap_err_with(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Write a snapshot to `path`, creating its parent directories if necessary.
+///
+/// The snapshot is written to a temporary file first, so that a crash can't
+/// leave a truncated snapshot behind.
+pub fn save(path: &Path, snapshot: &HistorySnapshot) -> Result<(), eyre::Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .wrap_err_with(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let temporary_path = path.with_extension("json.tmp");
+    let contents = serde_json::to_vec(snapshot)?;
+    std::fs::write(&temporary_path, contents)
+        .wrap_err_with(|| format!("Failed to write {}", temporary_path.display()))?;
+    std::fs::rename(&temporary_path, path)
+        .wrap_err_with(|| format!("Failed to replace {}", path.display()))
+}
+
+/// Like [`save`], but on a thread where blocking on the filesystem doesn't
+/// hold up the runtime.
+pub async fn save_in_background(
+    path: PathBuf,
+    snapshot: HistorySnapshot,
+) -> Result<(), eyre::Error> {
+    tokio::task::spawn_blocking(move || save(&path, &snapshot))
+        .await
+        .wrap_err("Failed to wait for the history to be saved")?
+}
+
+/// Periodically write the history of the [`ResourceMonitor`] to `path`, and
+/// a final time once cancelled.
+///
+/// Since this is the only writer of `path`, snapshots never overlap. Failures
+/// of the periodic snapshots are logged rather than returned, since a missing
+/// snapshot is no reason to stop serving metrics, only that of the final one
+/// is returned.
+#[tracing::instrument(skip(resource_monitor, cancellation_token))]
+pub async fn snapshot_thread(
+    resource_monitor: Arc<AsyncMutex<ResourceMonitor>>,
+    path: PathBuf,
+    snapshot_interval: Duration,
+    cancellation_token: CancellationToken,
+) -> Result<(), eyre::Error> {
+    loop {
+        tokio::select! {
+            () = tokio::time::sleep(snapshot_interval) => {}
+            () = cancellation_token.cancelled() => break,
+        }
+        let snapshot = resource_monitor.lock().await.snapshot();
+        if let Err(error) = save_in_background(path.clone(), snapshot).await {
+            tracing::warn!(?error, "Failed to save the history");
+        }
+    }
+
+    let snapshot = resource_monitor.lock().await.snapshot();
+    save_in_background(path, snapshot).await
+}
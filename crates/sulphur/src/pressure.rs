@@ -0,0 +1,80 @@
+//! Linux [pressure stall information][psi], i.e. how much time tasks spend
+//! waiting for a resource.
+//!
+//! [psi]: https://docs.kernel.org/accounting/psi.html
+
+use crate::units::Pressure;
+
+/// A resource the kernel reports the pressure of.
+#[derive(Clone, Copy, Debug)]
+pub enum PressureResource {
+    Cpu,
+    Memory,
+    Io,
+}
+
+impl PressureResource {
+    const fn path(self) -> &'static str {
+        match self {
+            Self::Cpu => "/proc/pressure/cpu",
+            Self::Memory => "/proc/pressure/memory",
+            Self::Io => "/proc/pressure/io",
+        }
+    }
+}
+
+/// Read the current pressure of `resource`.
+///
+/// Returns [`None`] if the kernel was built without PSI support, or if it was
+/// disabled with `psi=0`.
+#[must_use]
+pub fn read_pressure(resource: PressureResource) -> Option<Pressure> {
+    let contents = std::fs::read_to_string(resource.path()).ok()?;
+    parse_pressure(&contents)
+}
+
+/// Parse the ten-second averages out of a pressure file.
+///
+/// Older kernels don't report `full` pressure of the CPU, which is then
+/// considered to be zero.
+fn parse_pressure(contents: &str) -> Option<Pressure> {
+    let mut some = None;
+    let mut full = None;
+    for line in contents.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next()?;
+        let average = fields
+            .find_map(|field| field.strip_prefix("avg10="))?
+            .parse::<f32>()
+            .ok()?;
+        match kind {
+            "some" => some = Some(average),
+            "full" => full = Some(average),
+            _ => {}
+        }
+    }
+
+    Some(Pressure::from_percentages(some?, full.unwrap_or(0.)))
+}
+
+#[cfg(test)]
+mod tests {
+    use uom::si;
+
+    use super::parse_pressure;
+
+    #[test]
+    fn pressure_parsing() {
+        let contents = "some avg10=1.47 avg60=2.90 avg300=2.88 total=97178442\n\
+                        full avg10=0.50 avg60=0.00 avg300=0.00 total=0\n";
+        let pressure = parse_pressure(contents).unwrap();
+        assert!((pressure.some().get::<si::ratio::percent>() - 1.47).abs() <= 10e-6);
+        assert!((pressure.full().get::<si::ratio::percent>() - 0.5).abs() <= 10e-6);
+
+        let pressure = parse_pressure("some avg10=3.00 avg60=0.00 avg300=0.00 total=0\n").unwrap();
+        assert!(pressure.full().get::<si::ratio::percent>().abs() <= 10e-6);
+
+        assert!(parse_pressure("").is_none());
+        assert!(parse_pressure("some avg10=nope\n").is_none());
+    }
+}
@@ -0,0 +1,171 @@
+//! The processes that use the most CPU or memory right now, to find out who is
+//! behind a spike in the graphs.
+
+use std::fmt::Write as _;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use uom::si;
+
+use crate::units::CpuUsage;
+
+/// Which resource to rank processes by.
+#[derive(ValueEnum, Serialize, Deserialize, Default, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessSort {
+    #[default]
+    Cpu,
+    Mem,
+}
+
+/// How the client presents the top processes.
+#[derive(ValueEnum, Default, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ProcessesFormat {
+    /// An aligned table with a header, see [`format_table`].
+    #[default]
+    Table,
+    /// One compact line per process, see [`format_tooltip`].
+    Tooltip,
+}
+
+/// Instant resource usage of a single process.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[must_use]
+pub struct ProcessUsage {
+    pub pid: u32,
+    pub name: String,
+    /// Share of the whole machine, i.e. of all logical cores combined, so that
+    /// it adds up with the global CPU usage.
+    pub cpu_usage: CpuUsage,
+    /// Resident set size.
+    pub mem_usage: si::u64::Information,
+}
+
+/// The `count` processes using the most of the resource given by `by`, the
+/// heaviest first.
+pub fn top(
+    processes: impl IntoIterator<Item = ProcessUsage>,
+    by: ProcessSort,
+    count: usize,
+) -> Vec<ProcessUsage> {
+    let mut processes: Vec<ProcessUsage> = processes.into_iter().collect();
+    match by {
+        ProcessSort::Cpu => {
+            processes.sort_by(|a, b| b.cpu_usage.value.total_cmp(&a.cpu_usage.value));
+        }
+        ProcessSort::Mem => processes.sort_by(|a, b| b.mem_usage.cmp(&a.mem_usage)),
+    }
+    processes.truncate(count);
+    processes
+}
+
+/// An aligned table of `processes` with a header, for the terminal.
+pub fn format_table(processes: &[ProcessUsage]) -> Result<String, std::fmt::Error> {
+    let name_width = processes
+        .iter()
+        .map(|process| process.name.chars().count())
+        .chain(["NAME".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut table = String::new();
+    write!(
+        &mut table,
+        "{:>7}  {:<name_width$}  {:>6}  {:>10}",
+        "PID", "NAME", "CPU", "MEM"
+    )?;
+    for process in processes {
+        write!(
+            &mut table,
+            "\n{:>7}  {:<name_width$}  {:>5.1}%  {:>6.0} MiB",
+            process.pid,
+            process.name,
+            process.cpu_usage.get::<si::ratio::percent>(),
+            process.mem_usage.get::<si::information::mebibyte>(),
+        )?;
+    }
+
+    Ok(table)
+}
+
+/// One process per line with just the resource ranked `by`, compact enough
+/// for a status bar tooltip.
+pub fn format_tooltip(
+    processes: &[ProcessUsage],
+    by: ProcessSort,
+) -> Result<String, std::fmt::Error> {
+    let mut tooltip = String::new();
+    for (index, process) in processes.iter().enumerate() {
+        if index > 0 {
+            tooltip.push('\n');
+        }
+        match by {
+            ProcessSort::Cpu => write!(
+                &mut tooltip,
+                "{} {:.1}%",
+                process.name,
+                process.cpu_usage.get::<si::ratio::percent>()
+            )?,
+            ProcessSort::Mem => write!(
+                &mut tooltip,
+                "{} {:.0} MiB",
+                process.name,
+                process.mem_usage.get::<si::information::mebibyte>()
+            )?,
+        }
+    }
+
+    Ok(tooltip)
+}
+
+impl ProcessesFormat {
+    pub fn format(
+        self,
+        processes: &[ProcessUsage],
+        by: ProcessSort,
+    ) -> Result<String, std::fmt::Error> {
+        match self {
+            Self::Table => format_table(processes),
+            Self::Tooltip => format_tooltip(processes, by),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uom::si;
+
+    use super::{ProcessSort, ProcessUsage, format_tooltip, top};
+    use crate::units::CpuUsage;
+
+    fn process(pid: u32, name: &str, cpu_percentage: f32, mebibytes: u64) -> ProcessUsage {
+        ProcessUsage {
+            pid,
+            name: name.to_owned(),
+            cpu_usage: CpuUsage::from_percentage(cpu_percentage),
+            mem_usage: si::u64::Information::new::<si::information::mebibyte>(mebibytes),
+        }
+    }
+
+    #[test]
+    fn top_processes() {
+        let processes = [
+            process(1, "init", 0.5, 10),
+            process(2, "firefox", 20., 900),
+            process(3, "cargo", 60., 300),
+        ];
+
+        let by_cpu = top(processes.clone(), ProcessSort::Cpu, 2);
+        let pids: Vec<u32> = by_cpu.iter().map(|process| process.pid).collect();
+        assert_eq!(pids, [3, 2]);
+
+        let by_mem = top(processes, ProcessSort::Mem, 5);
+        let pids: Vec<u32> = by_mem.iter().map(|process| process.pid).collect();
+        assert_eq!(pids, [2, 3, 1]);
+
+        assert_eq!(
+            format_tooltip(&by_mem[..2], ProcessSort::Mem).unwrap(),
+            "firefox 900 MiB\ncargo 300 MiB"
+        );
+    }
+}
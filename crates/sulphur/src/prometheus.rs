@@ -0,0 +1,430 @@
+//! [Prometheus text exposition format][format] of the newest measurements.
+//!
+//! [format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use uom::si;
+
+use crate::collector::{CpuCollector, NetCollector};
+use crate::resource_monitor::{MeasurementType, Metrics, NetTotals, Series};
+use crate::units::{DiskIoRate, FsUsage, MemUsage, Pressure};
+
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[derive(Clone, Copy, Debug)]
+enum MetricKind {
+    Gauge,
+    Counter,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Direction {
+    Receive,
+    Transmit,
+}
+
+/// Accumulates metric families in the exposition format.
+#[derive(Default, Debug)]
+struct Exposition {
+    buffer: String,
+}
+
+/// Render the newest measurement of every series in `metrics`, along with the
+/// total network usage counters.
+pub fn render(
+    metrics: &Metrics,
+    net_totals: &BTreeMap<String, NetTotals>,
+) -> Result<String, std::fmt::Error> {
+    let mut exposition = Exposition::default();
+
+    let percentage_ratio = |percentage: &f32| f64::from(*percentage) / 100.;
+    let cpu_usage = metrics.series.get(CpuCollector::NAME);
+    if let Some(cpu_usage) = cpu_usage.and_then(|series| series.values.first()) {
+        exposition.family(
+            "sulphur_cpu_usage_ratio",
+            MetricKind::Gauge,
+            "Global CPU usage",
+        )?;
+        exposition.sample("sulphur_cpu_usage_ratio", &[], percentage_ratio(cpu_usage))?;
+    }
+
+    if metrics.cpu_core_usage().next().is_some() {
+        exposition.family(
+            "sulphur_cpu_core_usage_ratio",
+            MetricKind::Gauge,
+            "CPU usage of a single logical core",
+        )?;
+        for (core, core_usage) in metrics.cpu_core_usage().enumerate() {
+            if let Some(core_usage) = core_usage.values.first() {
+                exposition.sample(
+                    "sulphur_cpu_core_usage_ratio",
+                    &[("core", &core.to_string())],
+                    percentage_ratio(core_usage),
+                )?;
+            }
+        }
+    }
+
+    exposition.mem_usage("memory", metrics.mem_usage.first())?;
+    exposition.mem_usage("swap", metrics.swap_usage.first())?;
+    for direction in [Direction::Receive, Direction::Transmit] {
+        exposition.net_usage_rates(&metrics.series, direction)?;
+    }
+    for direction in [Direction::Receive, Direction::Transmit] {
+        exposition.net_totals(net_totals, direction)?;
+    }
+    exposition.disk_io_rate("read", metrics.disk.disk_read_rate.first())?;
+    exposition.disk_io_rate("written", metrics.disk.disk_write_rate.first())?;
+    exposition.filesystems(&metrics.filesystems)?;
+
+    if let Some(temperature) = metrics.temperature.first() {
+        exposition.family(
+            "sulphur_temperature_celsius",
+            MetricKind::Gauge,
+            "Temperature of the hottest sensor",
+        )?;
+        exposition.sample(
+            "sulphur_temperature_celsius",
+            &[],
+            temperature
+                .get::<si::thermodynamic_temperature::degree_celsius>()
+                .into(),
+        )?;
+    }
+
+    if let Some(load_average) = metrics.load_average.first() {
+        exposition.family(
+            "sulphur_load_average_per_core",
+            MetricKind::Gauge,
+            "Load average divided by the number of logical cores",
+        )?;
+        for (window, load) in [
+            ("1m", load_average.one()),
+            ("5m", load_average.five()),
+            ("15m", load_average.fifteen()),
+        ] {
+            exposition.sample(
+                "sulphur_load_average_per_core",
+                &[("window", window)],
+                load.into(),
+            )?;
+        }
+    }
+
+    exposition.pressure("cpu", metrics.cpu_pressure.first())?;
+    exposition.pressure("memory", metrics.memory_pressure.first())?;
+    exposition.pressure("io", metrics.io_pressure.first())?;
+
+    exposition.named_series(
+        &metrics.series,
+        MeasurementType::Command,
+        "Newest number printed by an external command",
+    )?;
+    exposition.named_series(
+        &metrics.series,
+        MeasurementType::Custom,
+        "Newest value pushed to a custom series",
+    )?;
+
+    Ok(exposition.buffer)
+}
+
+impl Exposition {
+    fn family(&mut self, name: &str, kind: MetricKind, help: &str) -> std::fmt::Result {
+        let kind = match kind {
+            MetricKind::Gauge => "gauge",
+            MetricKind::Counter => "counter",
+        };
+        writeln!(&mut self.buffer, "# HELP {name} {help}")?;
+        writeln!(&mut self.buffer, "# TYPE {name} {kind}")
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) -> std::fmt::Result {
+        write!(&mut self.buffer, "{name}")?;
+        for (index, (label, label_value)) in labels.iter().enumerate() {
+            let separator = if index == 0 { '{' } else { ',' };
+            write!(&mut self.buffer, "{separator}{label}=\"")?;
+            for character in label_value.chars() {
+                match character {
+                    '\\' => write!(&mut self.buffer, "\\\\")?,
+                    '"' => write!(&mut self.buffer, "\\\"")?,
+                    '\n' => write!(&mut self.buffer, "\\n")?,
+                    character => write!(&mut self.buffer, "{character}")?,
+                }
+            }
+            write!(&mut self.buffer, "\"")?;
+        }
+        if !labels.is_empty() {
+            write!(&mut self.buffer, "}}")?;
+        }
+        writeln!(&mut self.buffer, " {value}")
+    }
+
+    fn mem_usage(&mut self, kind: &str, mem_usage: Option<&MemUsage>) -> std::fmt::Result {
+        let Some(mem_usage) = mem_usage else {
+            return Ok(());
+        };
+
+        let used = format!("sulphur_{kind}_used_bytes");
+        let total = format!("sulphur_{kind}_total_bytes");
+        exposition_bytes(self, &used, &format!("Used {kind}"), mem_usage.used())?;
+        exposition_bytes(self, &total, &format!("Total {kind}"), mem_usage.total())
+    }
+
+    fn disk_io_rate(&mut self, participle: &str, rate: Option<&DiskIoRate>) -> std::fmt::Result {
+        let Some(rate) = rate else {
+            return Ok(());
+        };
+
+        let name = format!("sulphur_disk_{participle}_bytes_per_second");
+        let help = format!("Rate of data {participle} by all disks");
+        self.family(&name, MetricKind::Gauge, &help)?;
+        let bytes_per_second = rate
+            .as_information_rate()
+            .get::<si::information_rate::byte_per_second>();
+        self.sample(&name, &[], bytes_per_second.into())
+    }
+
+    fn pressure(&mut self, resource: &str, pressure: Option<&Pressure>) -> std::fmt::Result {
+        let Some(pressure) = pressure else {
+            return Ok(());
+        };
+
+        let name = format!("sulphur_{resource}_pressure_ratio");
+        let help = format!("Share of the last ten seconds in which tasks stalled on {resource}");
+        self.family(&name, MetricKind::Gauge, &help)?;
+        for (kind, share) in [("some", pressure.some()), ("full", pressure.full())] {
+            self.sample(
+                &name,
+                &[("kind", kind)],
+                share.get::<si::ratio::ratio>().into(),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn filesystems(&mut self, filesystems: &BTreeMap<String, FsUsage>) -> std::fmt::Result {
+        if filesystems.is_empty() {
+            return Ok(());
+        }
+
+        self.filesystem_family(
+            "sulphur_filesystem_used_bytes",
+            "Used space of a filesystem",
+            filesystems,
+            FsUsage::used,
+        )?;
+        self.filesystem_family(
+            "sulphur_filesystem_size_bytes",
+            "Total space of a filesystem",
+            filesystems,
+            FsUsage::total,
+        )
+    }
+
+    #[expect(clippy::cast_precision_loss)]
+    fn filesystem_family(
+        &mut self,
+        name: &str,
+        help: &str,
+        filesystems: &BTreeMap<String, FsUsage>,
+        information: impl Fn(&FsUsage) -> si::u64::Information,
+    ) -> std::fmt::Result {
+        self.family(name, MetricKind::Gauge, help)?;
+        for (mount_point, usage) in filesystems {
+            let bytes = information(usage).get::<si::information::byte>();
+            self.sample(name, &[("mountpoint", mount_point)], bytes as f64)?;
+        }
+
+        Ok(())
+    }
+
+    /// Collector series are in Mbit/s, see [`NetCollector`].
+    fn net_usage_rates(
+        &mut self,
+        series: &BTreeMap<String, Series>,
+        direction: Direction,
+    ) -> std::fmt::Result {
+        const BYTES_PER_MEGABIT: f64 = 1_000_000. / 8.;
+
+        let name = format!("sulphur_network_{}_bytes_per_second", direction.name());
+        let direction_series = match direction {
+            Direction::Receive => NetCollector::RECEIVED,
+            Direction::Transmit => NetCollector::TRANSMITTED,
+        };
+        let rates: Vec<(&str, f32)> = series
+            .iter()
+            .filter_map(|(series_name, net_usage)| {
+                let (unqualified, interface) = series_name.split_once('/')?;
+                let rate = net_usage.values.first()?;
+                (unqualified == direction_series).then_some((interface, *rate))
+            })
+            .collect();
+        if rates.is_empty() {
+            return Ok(());
+        }
+
+        let help = format!(
+            "Rate of data {} by a network interface",
+            direction.participle()
+        );
+        self.family(&name, MetricKind::Gauge, &help)?;
+        for (interface, megabits_per_second) in rates {
+            let bytes_per_second = f64::from(megabits_per_second) * BYTES_PER_MEGABIT;
+            self.sample(&name, &[("interface", interface)], bytes_per_second)?;
+        }
+
+        Ok(())
+    }
+
+    /// The series of a type that has one per name, labelled by that name.
+    /// Their units vary, so values are exposed as they are.
+    fn named_series(
+        &mut self,
+        series: &BTreeMap<String, Series>,
+        measurement_type: MeasurementType,
+        help: &str,
+    ) -> std::fmt::Result {
+        let type_name = measurement_type.name();
+        let values: Vec<(&str, f32)> = series
+            .iter()
+            .filter_map(|(series_name, named_series)| {
+                let (unqualified, name) = series_name.split_once('/')?;
+                let value = named_series.values.first()?;
+                (unqualified == type_name).then_some((name, *value))
+            })
+            .collect();
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        let name = format!("sulphur_{type_name}_value");
+        self.family(&name, MetricKind::Gauge, help)?;
+        for (series_name, value) in values {
+            self.sample(&name, &[("name", series_name)], value.into())?;
+        }
+
+        Ok(())
+    }
+
+    #[expect(clippy::cast_precision_loss)]
+    fn net_totals(
+        &mut self,
+        net_totals: &BTreeMap<String, NetTotals>,
+        direction: Direction,
+    ) -> std::fmt::Result {
+        if net_totals.is_empty() {
+            return Ok(());
+        }
+
+        let name = format!("sulphur_network_{}_bytes_total", direction.name());
+        let help = format!(
+            "Total data {} by a network interface",
+            direction.participle()
+        );
+        self.family(&name, MetricKind::Counter, &help)?;
+        for (interface, totals) in net_totals {
+            let usage = match direction {
+                Direction::Receive => totals.received,
+                Direction::Transmit => totals.transmitted,
+            };
+            let bytes = usage.get::<si::information::byte>();
+            self.sample(&name, &[("interface", interface)], bytes as f64)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Direction {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Receive => "receive",
+            Self::Transmit => "transmit",
+        }
+    }
+
+    const fn participle(self) -> &'static str {
+        match self {
+            Self::Receive => "received",
+            Self::Transmit => "transmitted",
+        }
+    }
+}
+
+#[expect(clippy::cast_precision_loss)]
+fn exposition_bytes(
+    exposition: &mut Exposition,
+    name: &str,
+    help: &str,
+    information: si::u64::Information,
+) -> std::fmt::Result {
+    exposition.family(name, MetricKind::Gauge, help)?;
+    let bytes = information.get::<si::information::byte>();
+    exposition.sample(name, &[], bytes as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::render;
+    use crate::grading::{NetUsageRateGrading, Thresholds};
+    use crate::resource_monitor::{Metrics, NetTotals, Series};
+    use crate::units::{MemUsage, NetUsage};
+
+    #[test]
+    fn prometheus_exposition() {
+        let cpu_usage = Series::cpu_usage(vec![25., 50.]);
+        let net_received_rate = Series {
+            unit: String::from(" Mbit/s"),
+            thresholds: NetUsageRateGrading::THRESHOLDS,
+            values: vec![0.5],
+        };
+        let queue_depth = Series {
+            unit: String::from(" jobs"),
+            thresholds: Thresholds::new(10., 100., 1000.).unwrap(),
+            values: vec![12.],
+        };
+        let metrics = Metrics {
+            series: BTreeMap::from([
+                (String::from("cpu"), cpu_usage),
+                (String::from("net-rx/eth0"), net_received_rate),
+                (String::from("command/queue"), queue_depth.clone()),
+                (String::from("custom/builds"), queue_depth),
+            ]),
+            mem_usage: vec![MemUsage::from_bytes(256, 1024)],
+            ..Metrics::default()
+        };
+        let net_totals = BTreeMap::from([(
+            String::from("eth0"),
+            NetTotals {
+                received: NetUsage::from_bytes(10),
+                transmitted: NetUsage::from_bytes(20),
+            },
+        )]);
+
+        let exposition = render(&metrics, &net_totals).unwrap();
+        let samples: Vec<&str> = exposition
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect();
+        assert_eq!(
+            samples,
+            [
+                "sulphur_cpu_usage_ratio 0.25",
+                "sulphur_memory_used_bytes 256",
+                "sulphur_memory_total_bytes 1024",
+                "sulphur_network_receive_bytes_per_second{interface=\"eth0\"} 62500",
+                "sulphur_network_receive_bytes_total{interface=\"eth0\"} 10",
+                "sulphur_network_transmit_bytes_total{interface=\"eth0\"} 20",
+                "sulphur_command_value{name=\"queue\"} 12",
+                "sulphur_custom_value{name=\"builds\"} 12",
+            ]
+        );
+        assert!(exposition.contains("# TYPE sulphur_network_receive_bytes_total counter\n"));
+    }
+}
@@ -1,21 +1,238 @@
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
 use clap::ValueEnum;
+use clap::builder::{PossibleValue, StringValueParser, TypedValueParser, ValueParserFactory};
+use clap::error::ErrorKind;
 use epicentre_diagnostics::tracing;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use itertools::Itertools;
 use ringbuffer::{AllocRingBuffer, RingBuffer};
 use serde::{Deserialize, Serialize};
-use sysinfo::{CpuRefreshKind, Networks, RefreshKind, System};
-use tokio::sync::Mutex as AsyncMutex;
+use sysinfo::{
+    Component, Components, CpuRefreshKind, DiskRefreshKind, Disks, MemoryRefreshKind, Networks,
+    ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System,
+};
+use tokio::sync::{Mutex as AsyncMutex, watch};
 use tokio_util::sync::CancellationToken;
+use uom::si;
 
-use crate::units::{CpuUsage, NetUsage, NetUsageRate};
+use crate::cgroup::{CgroupCounters, read_counters};
+use crate::collector::{
+    Collector, CpuCollector, NetCollector, SampleContext, is_valid_series_name, qualified_series,
+};
+use crate::grading::{CpuUsageGrading, GradedMeasurement, Thresholds};
+use crate::pressure::{PressureResource, read_pressure};
+use crate::processes::{ProcessSort, ProcessUsage};
+use crate::units::{
+    CpuUsage, DiskIo, DiskIoRate, FsUsage, LoadAverage, MemUsage, NetUsage, NetUsageRate, Pressure,
+    Temperature,
+};
 
-#[derive(ValueEnum, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(
+    ValueEnum, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug,
+)]
+#[serde(rename_all = "kebab-case")]
 pub enum MeasurementType {
     Cpu,
+    /// One graph per logical CPU core.
+    CpuCores,
+    /// The usage of the busiest CPU core at each point in time.
+    CpuMax,
+    /// Combined (received + transmitted) network usage.
     Net,
+    /// Received network usage.
+    NetRx,
+    /// Transmitted network usage.
+    NetTx,
+    /// Combined (read + written) disk throughput.
+    Disk,
+    /// Disk read throughput.
+    DiskRead,
+    /// Disk write throughput.
+    DiskWrite,
+    Mem,
+    Swap,
+    /// Used space of every (selected) mounted filesystem.
+    Fs,
+    /// Temperature of the hottest (selected) sensor.
+    Temp,
+    /// One-minute load average per logical core.
+    Load,
+    /// Share of time in which tasks were waiting for a CPU.
+    CpuPressure,
+    /// Share of time in which tasks were waiting for memory.
+    MemPressure,
+    /// Share of time in which tasks were waiting for I/O.
+    IoPressure,
+    /// The number printed by an external command configured on the server,
+    /// given as `command:NAME`.
+    Command,
+    /// A series pushed to the server over HTTP, given as `custom:NAME`.
+    Custom,
+}
+
+/// A [`MeasurementType`] as given on the command line, along with the name of
+/// the series to render for types that have a series per name, e.g.
+/// `command:queue`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct MeasurementSelector {
+    pub measurement_type: MeasurementType,
+    pub series: Option<String>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MeasurementSelectorError {
+    #[error("Unknown measurement type `{0}`")]
+    UnknownType(String),
+    #[error("`{0}` needs the name of a series, e.g. `{0}:NAME`")]
+    MissingSeries(String),
+    #[error("`{0}` has a single series, which can't be named")]
+    UnexpectedSeries(String),
+}
+
+/// Parses [`MeasurementSelector`]s, while still listing every
+/// [`MeasurementType`] in the help.
+#[derive(Clone, Copy, Debug)]
+pub struct MeasurementSelectorParser;
+
+impl MeasurementType {
+    /// The types that can be shown for a single cgroup, see
+    /// [`Metrics::with_cgroup`].
+    pub const CGROUP_TYPES: [Self; 5] = [
+        Self::Cpu,
+        Self::Mem,
+        Self::Disk,
+        Self::DiskRead,
+        Self::DiskWrite,
+    ];
+
+    /// The name of this type on the command line, which is also the name of the
+    /// collector series it is rendered from (if any).
+    #[must_use]
+    pub fn name(self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_owned())
+            .unwrap_or_default()
+    }
+
+    /// The type that the collector series called `name` is rendered as, e.g.
+    /// [`Self::NetRx`] for `net-rx/eth0`.
+    #[must_use]
+    pub fn of_series(name: &str) -> Option<Self> {
+        let unqualified = name.split_once('/').map_or(name, |(series, _)| series);
+        Self::from_str(unqualified, false).ok()
+    }
+
+    /// Whether this type has a series per name, e.g. per external command,
+    /// rather than a single one.
+    #[must_use]
+    pub const fn has_named_series(self) -> bool {
+        matches!(self, Self::Command | Self::Custom)
+    }
+}
+
+impl From<MeasurementType> for MeasurementSelector {
+    fn from(measurement_type: MeasurementType) -> Self {
+        Self {
+            measurement_type,
+            series: None,
+        }
+    }
+}
+
+impl FromStr for MeasurementSelector {
+    type Err = MeasurementSelectorError;
+
+    /// Parse a type such as `cpu`, or a named series such as `command:queue`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, series) = match s.split_once(':') {
+            Some((name, series)) => (name, Some(series.to_owned())),
+            None => (s, None),
+        };
+        let measurement_type = MeasurementType::from_str(name, false)
+            .map_err(|_| MeasurementSelectorError::UnknownType(name.to_owned()))?;
+        match (measurement_type.has_named_series(), &series) {
+            (true, None) => Err(MeasurementSelectorError::MissingSeries(name.to_owned())),
+            (false, Some(_)) => Err(MeasurementSelectorError::UnexpectedSeries(name.to_owned())),
+            _ => Ok(Self {
+                measurement_type,
+                series,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for MeasurementSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.measurement_type.name())?;
+        if let Some(series) = &self.series {
+            write!(f, ":{series}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ValueParserFactory for MeasurementSelector {
+    type Parser = MeasurementSelectorParser;
+
+    fn value_parser() -> Self::Parser {
+        MeasurementSelectorParser
+    }
+}
+
+impl TypedValueParser for MeasurementSelectorParser {
+    type Value = MeasurementSelector;
+
+    fn parse_ref(
+        &self,
+        command: &clap::Command,
+        argument: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = StringValueParser::new().parse_ref(command, argument, value)?;
+        value.parse().map_err(|error: MeasurementSelectorError| {
+            clap::Error::raw(ErrorKind::InvalidValue, format!("{error}\n")).with_cmd(command)
+        })
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            MeasurementType::value_variants()
+                .iter()
+                .filter_map(ValueEnum::to_possible_value),
+        ))
+    }
+}
+
+/// How finely grained the history of every series is.
+///
+/// Coarser resolutions average the realtime measurements over longer periods,
+/// which allows them to look back much further. The periods are configured on
+/// the server, the spans below are those of the defaults.
+#[derive(ValueEnum, Serialize, Deserialize, Default, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    /// Every measurement, spanning the configured lookback period.
+    #[default]
+    Realtime,
+    /// One-minute averages, spanning the last hour.
+    Minute,
+    /// One-hour averages, spanning the last week.
+    Hour,
+}
+
+/// A measurement that can be averaged over a longer period of time.
+pub trait Aggregate: Copy {
+    /// Combine consecutive measurements into one, or return [`None`] if there
+    /// are none.
+    fn aggregate(samples: &[Self]) -> Option<Self>;
 }
 
 #[derive(Debug)]
@@ -23,106 +240,1693 @@ pub enum MeasurementType {
 pub struct ResourceMonitor {
     system: System,
     networks: Networks,
+    disks: Disks,
+    components: Components,
 
+    interface_filter: InterfaceFilter,
+    /// Mount points of the filesystems to report, or all of them if empty.
+    mount_points: Vec<PathBuf>,
+    sensor_filter: SensorFilter,
+    /// Whether to refresh the usage of every process, which is fairly costly.
+    track_processes: bool,
+    /// The accounted cgroups, by their configured path.
+    cgroups: BTreeMap<String, CgroupHistory>,
+    collectors: Vec<Box<dyn Collector>>,
+
+    realtime_capacity: usize,
     update_intervals: UpdateIntervals,
     last_update: Instant,
+    last_minute_roll_up: Instant,
+    last_hour_roll_up: Instant,
 
-    cpu_usage: AllocRingBuffer<CpuUsage>,
-    net_usage_rate: AllocRingBuffer<NetUsageRate>,
+    /// History of every series sampled by the collectors, by name.
+    series: BTreeMap<String, SeriesHistory>,
+    /// Restored series that no collector has reported yet, e.g. commands that
+    /// haven't printed a number since the start, with when they were saved.
+    unclaimed_series: BTreeMap<String, (SystemTime, TieredSnapshot<SeriesSample>)>,
+    /// Series pushed over HTTP, by the name they were pushed as.
+    custom_series: BTreeMap<String, CustomSeriesHistory>,
+    /// How long custom series are kept without being pushed to.
+    custom_idle_time: Duration,
+    disk_io: DiskHistory,
+    mem_usage: TieredHistory<MemUsage>,
+    swap_usage: TieredHistory<MemUsage>,
+    temperature: TieredHistory<Temperature>,
+    load_average: TieredHistory<LoadAverage>,
+    cpu_pressure: TieredHistory<Pressure>,
+    memory_pressure: TieredHistory<Pressure>,
+    io_pressure: TieredHistory<Pressure>,
 }
 
 #[derive(Debug)]
 pub struct UpdateIntervals {
     pub realtime: Duration,
+    /// How often realtime measurements are averaged into the minute tier.
+    pub minute: Duration,
+    /// How often minute averages are averaged into the hour tier.
+    pub hour: Duration,
+}
+
+/// Decides which network interfaces are taken into account.
+///
+/// An interface is accepted if it matches any of the included globs (or if
+/// there are none) and does not match any of the excluded globs.
+#[derive(Default, Clone, Debug)]
+pub struct InterfaceFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+/// Decides which temperature sensors are taken into account, by their label.
+///
+/// A sensor is accepted if it matches any of the included globs, or if there
+/// are none.
+#[derive(Default, Debug)]
+pub struct SensorFilter {
+    include: Option<GlobSet>,
 }
 
+/// Amount of data received and transmitted by an interface since boot.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[must_use]
+pub struct NetTotals {
+    pub received: NetUsage,
+    pub transmitted: NetUsage,
+}
+
+/// The history of a single series at every [`Resolution`].
+#[derive(Debug)]
+struct TieredHistory<T> {
+    realtime: AllocRingBuffer<T>,
+    minute: AllocRingBuffer<T>,
+    hour: AllocRingBuffer<T>,
+    /// Realtime measurements not yet averaged into the minute tier.
+    pending_minute: Vec<T>,
+    /// Minute averages not yet averaged into the hour tier.
+    pending_hour: Vec<T>,
+}
+
+/// Everything the [`ResourceMonitor`] remembers, in a form that can be written
+/// to disk and restored after a restart.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[must_use]
+pub struct HistorySnapshot {
+    taken_at: SystemTime,
+    #[serde(default)]
+    series: BTreeMap<String, TieredSnapshot<SeriesSample>>,
+    #[serde(default)]
+    custom_series: BTreeMap<String, CustomSeriesSnapshot>,
+    #[serde(default)]
+    disk_io: DiskSnapshot,
+    mem_usage: TieredSnapshot<MemUsage>,
+    swap_usage: TieredSnapshot<MemUsage>,
+    #[serde(default)]
+    temperature: TieredSnapshot<Temperature>,
+    #[serde(default)]
+    load_average: TieredSnapshot<LoadAverage>,
+    #[serde(default)]
+    cpu_pressure: TieredSnapshot<Pressure>,
+    #[serde(default)]
+    memory_pressure: TieredSnapshot<Pressure>,
+    #[serde(default)]
+    io_pressure: TieredSnapshot<Pressure>,
+    #[serde(default)]
+    cgroups: BTreeMap<String, CgroupSnapshot>,
+    // Written before CPU and network usage were sampled by collectors, and
+    // turned into `series` when restored.
+    #[serde(default, skip_serializing)]
+    cpu_usage: Option<TieredSnapshot<CpuUsage>>,
+    #[serde(default, skip_serializing)]
+    cpu_core_usage: Vec<TieredSnapshot<CpuUsage>>,
+    #[serde(default, skip_serializing)]
+    net_usage: Option<NetSnapshot>,
+    #[serde(default, skip_serializing)]
+    net_interface_usage: BTreeMap<String, NetSnapshot>,
+}
+
+/// Every tier of a [`TieredHistory`], oldest measurements first.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct TieredSnapshot<T> {
+    realtime: Vec<T>,
+    minute: Vec<T>,
+    hour: Vec<T>,
+}
+
+/// Network usage as it was kept before the [`NetCollector`] sampled it.
+#[derive(Deserialize, Clone, Debug)]
+struct NetSnapshot {
+    combined: TieredSnapshot<NetUsageRate>,
+    received: TieredSnapshot<NetUsageRate>,
+    transmitted: TieredSnapshot<NetUsageRate>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+struct DiskSnapshot {
+    combined: TieredSnapshot<DiskIoRate>,
+    read: TieredSnapshot<DiskIoRate>,
+    written: TieredSnapshot<DiskIoRate>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct CgroupSnapshot {
+    cpu_usage: TieredSnapshot<CpuUsage>,
+    mem_usage: TieredSnapshot<MemUsage>,
+    disk_io: DiskSnapshot,
+}
+
+/// How many measurements each tier missed while a snapshot was on disk.
+#[derive(Clone, Copy, Debug)]
+struct MissedMeasurements {
+    realtime: usize,
+    minute: usize,
+    hour: usize,
+}
+
+/// History of a single series pushed over HTTP.
+#[derive(Debug)]
+struct CustomSeriesHistory {
+    unit: String,
+    thresholds: Thresholds,
+    last_push: Instant,
+    /// The newest value pushed, which is recorded at every realtime update.
+    /// [`None`] for a restored series until it is pushed to again.
+    latest: Option<f32>,
+    history: TieredHistory<f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct CustomSeriesSnapshot {
+    unit: String,
+    thresholds: Thresholds,
+    history: TieredSnapshot<f32>,
+}
+
+/// A measurement pushed to a custom series, e.g.
+/// `{"value": 42, "unit": " jobs", "thresholds": [10, 100, 1000]}`.
+///
+/// The unit and thresholds of a series are those of its latest push that has
+/// them. A new series can't be graded without thresholds.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[must_use]
+pub struct CustomMeasurement {
+    pub value: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thresholds: Option<Thresholds>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CustomMeasurementError {
+    #[error("Values must be finite numbers")]
+    NotFinite,
+    #[error("The first measurement of a series needs thresholds")]
+    MissingThresholds,
+    #[error(
+        "Names must consist of 1 to {} ASCII letters, digits, `-`, `_` or `.`",
+        crate::collector::MAX_SERIES_NAME_LENGTH
+    )]
+    InvalidName,
+    #[error(
+        "There are already {} custom series, wait for some to expire",
+        ResourceMonitor::MAX_CUSTOM_SERIES
+    )]
+    TooManySeries,
+}
+
+/// History of a single collector series.
+#[derive(Debug)]
+struct SeriesHistory {
+    /// Index of the collector that samples this series.
+    collector: usize,
+    history: TieredHistory<SeriesSample>,
+}
+
+/// A value of a collector series, along with how long it was measured over,
+/// so that roll-ups of rates stay exact.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+struct SeriesSample {
+    value: f32,
+    seconds: f32,
+}
+
+/// Read, written and combined disk throughput history.
+#[derive(Debug)]
+struct DiskHistory {
+    combined: TieredHistory<DiskIoRate>,
+    read: TieredHistory<DiskIoRate>,
+    written: TieredHistory<DiskIoRate>,
+}
+
+/// CPU, memory and disk usage history of a single cgroup.
+#[derive(Debug)]
+struct CgroupHistory {
+    directory: PathBuf,
+    /// The counters as of the previous refresh, to compute rates from.
+    counters: Option<CgroupCounters>,
+    cpu_usage: TieredHistory<CpuUsage>,
+    mem_usage: TieredHistory<MemUsage>,
+    disk_io: DiskHistory,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[must_use]
 pub struct Metrics {
+    /// Every series sampled by a [`Collector`], by name.
+    #[serde(default)]
+    pub series: BTreeMap<String, Series>,
+    /// Throughput of all disks.
+    #[serde(flatten)]
+    pub disk: DiskMetrics,
+    #[serde(default)]
+    pub mem_usage: Vec<MemUsage>,
+    #[serde(default)]
+    pub swap_usage: Vec<MemUsage>,
+    /// Temperature of the hottest (selected) sensor.
+    #[serde(default)]
+    pub temperature: Vec<Temperature>,
+    #[serde(default)]
+    pub load_average: Vec<LoadAverage>,
+    /// Pressure stall information, empty if the kernel doesn't provide it.
+    #[serde(default)]
+    pub cpu_pressure: Vec<Pressure>,
+    #[serde(default)]
+    pub memory_pressure: Vec<Pressure>,
+    #[serde(default)]
+    pub io_pressure: Vec<Pressure>,
+    /// Current space usage of the (selected) filesystems, by mount point.
+    ///
+    /// Unlike the other measurements, this is not a series since it changes
+    /// far too slowly for a graph to be of any use.
+    #[serde(default)]
+    pub filesystems: BTreeMap<String, FsUsage>,
+    /// Usage of the accounted cgroups, by their configured path.
+    #[serde(default)]
+    pub cgroups: BTreeMap<String, CgroupMetrics>,
+    /// How many measurements a graph of these metrics spans. Series that were
+    /// recorded for a shorter time, see
+    /// [`ResourceMonitor::build_recorded_metrics`], are padded with idle
+    /// measurements when rendered.
+    #[serde(default)]
+    pub graph_samples: usize,
+}
+
+/// Measurements of a single collector series, newest first.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[must_use]
+pub struct Series {
+    /// The unit of every value, see [`Collector::unit`].
+    pub unit: String,
+    /// The thresholds the collector suggests grading values against.
+    pub thresholds: Thresholds,
+    pub values: Vec<f32>,
+}
+
+/// CPU, memory and disk usage of a single cgroup.
+///
+/// CPU usage is relative to the whole machine, and memory usage to the limit
+/// of the cgroup (or the whole memory if it isn't limited).
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[must_use]
+pub struct CgroupMetrics {
     #[serde(default)]
     pub cpu_usage: Vec<CpuUsage>,
     #[serde(default)]
-    pub net_usage_rate: Vec<NetUsageRate>,
+    pub mem_usage: Vec<MemUsage>,
+    #[serde(flatten)]
+    pub disk: DiskMetrics,
+}
+
+/// Read, written and combined disk throughput.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[must_use]
+pub struct DiskMetrics {
+    #[serde(default)]
+    pub disk_io_rate: Vec<DiskIoRate>,
+    #[serde(default)]
+    pub disk_read_rate: Vec<DiskIoRate>,
+    #[serde(default)]
+    pub disk_write_rate: Vec<DiskIoRate>,
 }
 
 impl ResourceMonitor {
     const REMOVE_NOT_LISTED_INTERFACES: bool = true;
+    const REMOVE_NOT_LISTED_DISKS: bool = true;
+    const REMOVE_NOT_LISTED_COMPONENTS: bool = true;
+    const REMOVE_DEAD_PROCESSES: bool = true;
+
+    /// How long custom series are kept without being pushed to, unless
+    /// configured otherwise.
+    pub const DEFAULT_CUSTOM_IDLE_TIME: Duration = Duration::from_secs(5 * 60);
+
+    /// How many custom series may be kept at once, since every one of them
+    /// holds a history at every resolution.
+    pub const MAX_CUSTOM_SERIES: usize = 64;
 
     fn system_refresh_specifics() -> RefreshKind {
-        RefreshKind::nothing().with_cpu(CpuRefreshKind::everything())
+        RefreshKind::nothing()
+            .with_cpu(CpuRefreshKind::everything())
+            .with_memory(MemoryRefreshKind::everything())
     }
 
-    pub fn new(capacity: usize, refresh_intervals: UpdateIntervals) -> Self {
+    fn disk_refresh_specifics() -> DiskRefreshKind {
+        DiskRefreshKind::nothing().with_io_usage().with_storage()
+    }
+
+    pub fn new(
+        capacity: usize,
+        refresh_intervals: UpdateIntervals,
+        interface_filter: InterfaceFilter,
+        mount_points: Vec<PathBuf>,
+        sensor_filter: SensorFilter,
+        track_processes: bool,
+        cgroups: &[PathBuf],
+    ) -> Self {
         let system = System::new_with_specifics(Self::system_refresh_specifics());
         let networks = Networks::new_with_refreshed_list();
+        let disks = Disks::new_with_refreshed_list_specifics(Self::disk_refresh_specifics());
+        let components = Components::new_with_refreshed_list();
 
-        let mut cpu_usage = AllocRingBuffer::new(capacity);
-        let mut net_usage_rate = AllocRingBuffer::new(capacity);
+        let mut mem_usage = TieredHistory::new(capacity);
+        let mut swap_usage = TieredHistory::new(capacity);
 
-        let cpu_usage_value = CpuUsage::from_percentage(system.global_cpu_usage());
-        let net_usage_rate_shim =
-            NetUsageRate::from_usage_and_duration(NetUsage::from_bytes(0), Duration::from_secs(0));
+        mem_usage.enqueue(Self::current_mem_usage(&system));
+        swap_usage.enqueue(Self::current_swap_usage(&system));
+
+        let mut temperature = TieredHistory::new(capacity);
+        if sensor_filter.include.is_some()
+            && !components
+                .iter()
+                .any(|component| sensor_filter.matches(component.label()))
+        {
+            let sensors: Vec<_> = components.iter().map(Component::label).collect();
+            tracing::warn!(?sensors, "No temperature sensor matches `--sensor`");
+        }
+        if let Some(hottest) = Self::hottest_temperature(&components, &sensor_filter) {
+            temperature.enqueue(hottest);
+        }
 
-        cpu_usage.enqueue(cpu_usage_value);
-        net_usage_rate.enqueue(net_usage_rate_shim);
+        let mut load_average = TieredHistory::new(capacity);
+        load_average.enqueue(Self::current_load_average(&system));
+        let pressure_history = |resource| {
+            let mut history = TieredHistory::new(capacity);
+            if let Some(pressure) = read_pressure(resource) {
+                history.enqueue(pressure);
+            }
+            history
+        };
 
-        Self {
+        let cgroups = cgroups
+            .iter()
+            .map(|cgroup| {
+                let history = CgroupHistory::new(capacity, crate::cgroup::resolve(cgroup));
+                (cgroup.display().to_string(), history)
+            })
+            .collect();
+
+        let collectors: Vec<Box<dyn Collector>> = vec![
+            Box::new(CpuCollector),
+            Box::new(NetCollector::new(interface_filter.clone())),
+        ];
+
+        let mut resource_monitor = Self {
             system,
             networks,
+            disks,
+            components,
+            interface_filter,
+            mount_points,
+            sensor_filter,
+            track_processes,
+            cgroups,
+            collectors,
+            realtime_capacity: capacity,
             update_intervals: refresh_intervals,
             last_update: Instant::now(),
-            cpu_usage,
-            net_usage_rate,
+            last_minute_roll_up: Instant::now(),
+            last_hour_roll_up: Instant::now(),
+            series: BTreeMap::new(),
+            unclaimed_series: BTreeMap::new(),
+            custom_series: BTreeMap::new(),
+            custom_idle_time: Self::DEFAULT_CUSTOM_IDLE_TIME,
+            disk_io: DiskHistory::new(capacity),
+            mem_usage,
+            swap_usage,
+            temperature,
+            load_average,
+            cpu_pressure: pressure_history(PressureResource::Cpu),
+            memory_pressure: pressure_history(PressureResource::Memory),
+            io_pressure: pressure_history(PressureResource::Io),
+        };
+        resource_monitor.sample_collectors(Duration::ZERO);
+        resource_monitor
+    }
+
+    /// Sample `collector` along with the built-in ones from the next refresh
+    /// on.
+    pub fn add_collector(&mut self, collector: Box<dyn Collector>) {
+        self.collectors.push(collector);
+    }
+
+    /// How many measurements the realtime tier keeps, which is as long as
+    /// graphs are at any resolution.
+    #[must_use]
+    pub const fn realtime_capacity(&self) -> usize {
+        self.realtime_capacity
+    }
+
+    /// Forget custom series that haven't been pushed to for `idle_time`.
+    pub const fn set_custom_idle_time(&mut self, idle_time: Duration) {
+        self.custom_idle_time = idle_time;
+    }
+
+    /// Record a measurement of the custom series `name`, creating it if need
+    /// be.
+    ///
+    /// Like the samples of collectors, the newest value is recorded once per
+    /// realtime update, however often it is pushed, so that custom graphs
+    /// span as long as the others. New series are refused once there are
+    /// [`Self::MAX_CUSTOM_SERIES`].
+    pub fn push_custom(
+        &mut self,
+        name: &str,
+        measurement: CustomMeasurement,
+    ) -> Result<(), CustomMeasurementError> {
+        if !is_valid_series_name(name) {
+            return Err(CustomMeasurementError::InvalidName);
+        }
+        if !measurement.value.is_finite() {
+            return Err(CustomMeasurementError::NotFinite);
+        }
+
+        let series_count = self.custom_series.len();
+        let series = match self.custom_series.entry(name.to_owned()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(_) if series_count >= Self::MAX_CUSTOM_SERIES => {
+                return Err(CustomMeasurementError::TooManySeries);
+            }
+            Entry::Vacant(entry) => {
+                let thresholds = measurement
+                    .thresholds
+                    .ok_or(CustomMeasurementError::MissingThresholds)?;
+                entry.insert(CustomSeriesHistory {
+                    unit: String::new(),
+                    thresholds,
+                    last_push: Instant::now(),
+                    latest: None,
+                    history: TieredHistory::new(self.realtime_capacity),
+                })
+            }
+        };
+
+        if let Some(unit) = measurement.unit {
+            series.unit = unit;
         }
+        if let Some(thresholds) = measurement.thresholds {
+            series.thresholds = thresholds;
+        }
+        series.last_push = Instant::now();
+        series.latest = Some(measurement.value);
+        Ok(())
+    }
+
+    fn current_mem_usage(system: &System) -> MemUsage {
+        MemUsage::from_bytes(system.used_memory(), system.total_memory())
+    }
+
+    fn current_swap_usage(system: &System) -> MemUsage {
+        MemUsage::from_bytes(system.used_swap(), system.total_swap())
+    }
+
+    #[expect(clippy::cast_possible_truncation)]
+    #[expect(clippy::cast_precision_loss)]
+    fn current_load_average(system: &System) -> LoadAverage {
+        let load_average = System::load_average();
+        let cores = system.cpus().len().max(1) as f64;
+        LoadAverage::per_core(
+            (load_average.one / cores) as f32,
+            (load_average.five / cores) as f32,
+            (load_average.fifteen / cores) as f32,
+        )
+    }
+
+    /// The temperature of the hottest accepted sensor, if there are any.
+    fn hottest_temperature(
+        components: &Components,
+        sensor_filter: &SensorFilter,
+    ) -> Option<Temperature> {
+        components
+            .iter()
+            .filter(|component| sensor_filter.matches(component.label()))
+            .filter_map(Component::temperature)
+            .filter(|degrees| degrees.is_finite())
+            .max_by(f32::total_cmp)
+            .map(Temperature::from_celsius)
     }
 
     pub fn refresh_realtime(&mut self) {
         self.system
             .refresh_specifics(Self::system_refresh_specifics());
-        self.cpu_usage
-            .enqueue(CpuUsage::from_percentage(self.system.global_cpu_usage()));
+        if self.track_processes {
+            self.system.refresh_processes_specifics(
+                ProcessesToUpdate::All,
+                Self::REMOVE_DEAD_PROCESSES,
+                ProcessRefreshKind::nothing().with_cpu().with_memory(),
+            );
+        }
+        self.mem_usage
+            .enqueue(Self::current_mem_usage(&self.system));
+        self.swap_usage
+            .enqueue(Self::current_swap_usage(&self.system));
 
-        let combined_net_usage = self
-            .networks
-            .values()
-            .map(|nd| nd.received() + nd.transmitted())
-            .map(NetUsage::from_bytes)
-            .sum::<NetUsage>();
-        let combined_net_usage_rate =
-            NetUsageRate::from_usage_and_duration(combined_net_usage, self.last_update.elapsed());
-        self.net_usage_rate.enqueue(combined_net_usage_rate);
+        // Machines without sensors (e.g. virtual ones) just have no series.
+        self.components.refresh(Self::REMOVE_NOT_LISTED_COMPONENTS);
+        if let Some(hottest) = Self::hottest_temperature(&self.components, &self.sensor_filter) {
+            self.temperature.enqueue(hottest);
+        }
+
+        self.load_average
+            .enqueue(Self::current_load_average(&self.system));
+        let pressure_histories = [
+            (PressureResource::Cpu, &mut self.cpu_pressure),
+            (PressureResource::Memory, &mut self.memory_pressure),
+            (PressureResource::Io, &mut self.io_pressure),
+        ];
+        for (resource, history) in pressure_histories {
+            if let Some(pressure) = read_pressure(resource) {
+                history.enqueue(pressure);
+            }
+        }
+
+        let elapsed = self.last_update.elapsed();
+        self.sample_collectors(elapsed);
         self.networks.refresh(Self::REMOVE_NOT_LISTED_INTERFACES);
 
+        let custom_idle_time = self.custom_idle_time;
+        self.custom_series
+            .retain(|_, series| series.last_push.elapsed() < custom_idle_time);
+        for series in self.custom_series.values_mut() {
+            if let Some(latest) = series.latest {
+                series.history.enqueue(latest);
+            }
+        }
+
+        self.refresh_disk_io(elapsed);
+
+        let cores = self.system.cpus().len();
+        let total_memory = self.system.total_memory();
+        for history in self.cgroups.values_mut() {
+            history.refresh(elapsed, cores, total_memory);
+        }
+
         self.last_update = Instant::now();
+        self.roll_up_due_tiers();
     }
 
-    pub fn build_metrics(&self) -> Metrics {
-        let cpu_usage = self
-            .cpu_usage
+    /// Record a new measurement of every collector series, and forget the
+    /// series that the collectors no longer report.
+    ///
+    /// A series that appears for the first time picks up its restored
+    /// history, if any.
+    fn sample_collectors(&mut self, elapsed: Duration) {
+        let context = SampleContext {
+            system: &self.system,
+            networks: &self.networks,
+            elapsed,
+        };
+
+        let mut sampled = BTreeSet::new();
+        for (index, collector) in self.collectors.iter_mut().enumerate() {
+            for (name, value) in collector.sample(&context) {
+                let series = self.series.entry(name.clone()).or_insert_with(|| {
+                    let mut history = TieredHistory::new(self.realtime_capacity);
+                    if let Some((taken_at, snapshot)) = self.unclaimed_series.remove(&name) {
+                        history.restore(snapshot, self.update_intervals.missed_since(taken_at));
+                    }
+                    SeriesHistory {
+                        collector: index,
+                        history,
+                    }
+                });
+                series.history.enqueue(SeriesSample {
+                    value,
+                    seconds: elapsed.as_secs_f32(),
+                });
+                sampled.insert(name);
+            }
+        }
+        self.series.retain(|name, _| sampled.contains(name));
+
+        // Once the hour tier would have moved past it, nothing of a restored
+        // series would be left anyway.
+        let intervals = &self.update_intervals;
+        self.unclaimed_series.retain(|_, (taken_at, _)| {
+            intervals.missed_since(*taken_at).hour < TieredHistory::<f32>::HOUR_CAPACITY
+        });
+    }
+
+    fn refresh_disk_io(&mut self, elapsed: Duration) {
+        self.disks.refresh_specifics(
+            Self::REMOVE_NOT_LISTED_DISKS,
+            Self::disk_refresh_specifics(),
+        );
+
+        // The same device may be mounted several times, but its I/O must only
+        // be counted once.
+        let device_usage: BTreeMap<_, _> = self
+            .disks
             .iter()
-            .copied()
-            .rev()
-            .pad_using(self.cpu_usage.capacity(), |_| CpuUsage::from_percentage(0.))
+            .map(|disk| (disk.name(), disk.usage()))
             .collect();
+        let read = device_usage
+            .values()
+            .map(|usage| DiskIo::from_bytes(usage.read_bytes))
+            .sum();
+        let written = device_usage
+            .values()
+            .map(|usage| DiskIo::from_bytes(usage.written_bytes))
+            .sum();
 
-        let net_usage_rate = self
-            .net_usage_rate
+        self.disk_io.enqueue(read, written, elapsed);
+    }
+
+    fn filesystem_usage(&self) -> BTreeMap<String, FsUsage> {
+        self.disks
+            .iter()
+            .filter(|disk| {
+                self.mount_points.is_empty()
+                    || self
+                        .mount_points
+                        .iter()
+                        .any(|mount_point| mount_point == disk.mount_point())
+            })
+            .map(|disk| {
+                let used = disk.total_space().saturating_sub(disk.available_space());
+                let usage = FsUsage::from_bytes(used, disk.total_space());
+                (disk.mount_point().display().to_string(), usage)
+            })
+            .collect()
+    }
+
+    /// Average the finer tiers into the coarser ones, if their time has come.
+    fn roll_up_due_tiers(&mut self) {
+        if self.last_minute_roll_up.elapsed() >= self.update_intervals.minute {
+            self.roll_up(Resolution::Minute);
+            self.last_minute_roll_up = Instant::now();
+        }
+        if self.last_hour_roll_up.elapsed() >= self.update_intervals.hour {
+            self.roll_up(Resolution::Hour);
+            self.last_hour_roll_up = Instant::now();
+        }
+    }
+
+    fn roll_up(&mut self, resolution: Resolution) {
+        for series in self.series.values_mut() {
+            series.history.roll_up(resolution);
+        }
+        for series in self.custom_series.values_mut() {
+            series.history.roll_up(resolution);
+        }
+        self.disk_io.roll_up(resolution);
+        self.mem_usage.roll_up(resolution);
+        self.swap_usage.roll_up(resolution);
+        self.temperature.roll_up(resolution);
+        self.load_average.roll_up(resolution);
+        self.cpu_pressure.roll_up(resolution);
+        self.memory_pressure.roll_up(resolution);
+        self.io_pressure.roll_up(resolution);
+        for history in self.cgroups.values_mut() {
+            history.roll_up(resolution);
+        }
+    }
+
+    /// Total network usage of every (filtered) interface since boot.
+    #[must_use]
+    pub fn net_totals(&self) -> BTreeMap<String, NetTotals> {
+        self.networks
+            .iter()
+            .filter(|(name, _)| self.interface_filter.matches(name))
+            .map(|(name, network_data)| {
+                let totals = NetTotals {
+                    received: NetUsage::from_bytes(network_data.total_received()),
+                    transmitted: NetUsage::from_bytes(network_data.total_transmitted()),
+                };
+                (name.clone(), totals)
+            })
+            .collect()
+    }
+
+    /// The `count` processes using the most of the resource given by `by`, or
+    /// [`None`] if processes aren't tracked.
+    ///
+    /// Threads are left out, their usage is already part of their process.
+    #[must_use]
+    #[expect(clippy::cast_precision_loss)]
+    pub fn top_processes(&self, by: ProcessSort, count: usize) -> Option<Vec<ProcessUsage>> {
+        if !self.track_processes {
+            return None;
+        }
+
+        let cores = self.system.cpus().len().max(1) as f32;
+        let processes = self
+            .system
+            .processes()
+            .values()
+            .filter(|process| process.thread_kind().is_none())
+            .map(|process| ProcessUsage {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string_lossy().into_owned(),
+                cpu_usage: CpuUsage::from_percentage(process.cpu_usage() / cores),
+                mem_usage: si::u64::Information::new::<si::information::byte>(process.memory()),
+            });
+        Some(crate::processes::top(processes, by, count))
+    }
+
+    pub fn snapshot(&self) -> HistorySnapshot {
+        HistorySnapshot {
+            taken_at: SystemTime::now(),
+            series: self
+                .series
+                .iter()
+                .map(|(name, series)| (name.clone(), series.history.snapshot()))
+                .collect(),
+            custom_series: self
+                .custom_series
+                .iter()
+                .map(|(name, series)| {
+                    let series_snapshot = CustomSeriesSnapshot {
+                        unit: series.unit.clone(),
+                        thresholds: series.thresholds,
+                        history: series.history.snapshot(),
+                    };
+                    (name.clone(), series_snapshot)
+                })
+                .collect(),
+            disk_io: self.disk_io.snapshot(),
+            mem_usage: self.mem_usage.snapshot(),
+            swap_usage: self.swap_usage.snapshot(),
+            temperature: self.temperature.snapshot(),
+            load_average: self.load_average.snapshot(),
+            cpu_pressure: self.cpu_pressure.snapshot(),
+            memory_pressure: self.memory_pressure.snapshot(),
+            io_pressure: self.io_pressure.snapshot(),
+            cgroups: self
+                .cgroups
+                .iter()
+                .map(|(name, history)| (name.clone(), history.snapshot()))
+                .collect(),
+            cpu_usage: None,
+            cpu_core_usage: Vec::new(),
+            net_usage: None,
+            net_interface_usage: BTreeMap::new(),
+        }
+    }
+
+    /// Prepend the measurements of a previous run to the current history.
+    ///
+    /// Measurements that would have fallen out of their tier by now are
+    /// discarded. Series that no collector reports yet are restored once one
+    /// does, which is how commands get their history back. Custom series are
+    /// kept for the idle time again, but only recorded once pushed to.
+    pub fn restore(&mut self, mut snapshot: HistorySnapshot) {
+        let missed = self.update_intervals.missed_since(snapshot.taken_at);
+        snapshot.migrate_legacy_series(&self.update_intervals);
+        tracing::debug!(taken_at = ?snapshot.taken_at, ?missed, "Restoring history");
+
+        for (name, series) in &mut self.series {
+            if let Some(series_snapshot) = snapshot.series.remove(name) {
+                series.history.restore(series_snapshot, missed);
+            }
+        }
+        self.unclaimed_series = snapshot
+            .series
+            .into_iter()
+            .map(|(name, series_snapshot)| (name, (snapshot.taken_at, series_snapshot)))
+            .collect();
+        for (name, series_snapshot) in snapshot.custom_series {
+            let mut history = TieredHistory::new(self.realtime_capacity);
+            history.restore(series_snapshot.history, missed);
+            let series = CustomSeriesHistory {
+                unit: series_snapshot.unit,
+                thresholds: series_snapshot.thresholds,
+                last_push: Instant::now(),
+                latest: None,
+                history,
+            };
+            self.custom_series.insert(name, series);
+        }
+        self.disk_io.restore(snapshot.disk_io, missed);
+        self.mem_usage.restore(snapshot.mem_usage, missed);
+        self.swap_usage.restore(snapshot.swap_usage, missed);
+        self.temperature.restore(snapshot.temperature, missed);
+        self.load_average.restore(snapshot.load_average, missed);
+        self.cpu_pressure.restore(snapshot.cpu_pressure, missed);
+        self.memory_pressure
+            .restore(snapshot.memory_pressure, missed);
+        self.io_pressure.restore(snapshot.io_pressure, missed);
+        for (name, history) in &mut self.cgroups {
+            if let Some(cgroup_snapshot) = snapshot.cgroups.remove(name) {
+                history.restore(cgroup_snapshot, missed);
+            }
+        }
+    }
+
+    /// Collect every series newest-first, padded with idle measurements up to
+    /// the capacity of the requested tier.
+    pub fn build_metrics(&self, resolution: Resolution) -> Metrics {
+        self.collect_metrics(resolution, true)
+    }
+
+    /// Like [`Self::build_metrics`], but only with measurements that were
+    /// actually taken, e.g. for statistics that padding would skew.
+    pub fn build_recorded_metrics(&self, resolution: Resolution) -> Metrics {
+        self.collect_metrics(resolution, false)
+    }
+
+    fn collect_metrics(&self, resolution: Resolution, padded: bool) -> Metrics {
+        let idle_pressure = || Pressure::from_percentages(0., 0.);
+        let graph_samples = match resolution {
+            Resolution::Realtime => self.realtime_capacity,
+            Resolution::Minute => TieredHistory::<f32>::MINUTE_CAPACITY,
+            Resolution::Hour => TieredHistory::<f32>::HOUR_CAPACITY,
+        };
+
+        let collector_series = self.series.iter().map(|(name, series)| {
+            let collector = &self.collectors[series.collector];
+            let series = Series {
+                unit: collector.unit().to_owned(),
+                thresholds: collector.default_thresholds(),
+                values: series
+                    .history
+                    .collect(resolution, padded, SeriesSample::idle)
+                    .iter()
+                    .map(|sample| sample.value)
+                    .collect(),
+            };
+            (name.clone(), series)
+        });
+        let custom_type = MeasurementType::Custom.name();
+        let custom_series = self.custom_series.iter().map(|(name, series)| {
+            let custom_series = Series {
+                unit: series.unit.clone(),
+                thresholds: series.thresholds,
+                values: series.history.collect(resolution, padded, || 0.),
+            };
+            (qualified_series(&custom_type, name), custom_series)
+        });
+        let series = collector_series.chain(custom_series).collect();
+
+        Metrics {
+            series,
+            disk: self.disk_io.build_metrics(resolution, padded),
+            mem_usage: self.mem_usage.collect(resolution, padded, MemUsage::unused),
+            swap_usage: self
+                .swap_usage
+                .collect(resolution, padded, MemUsage::unused),
+            temperature: self
+                .temperature
+                .collect_if_recorded(resolution, padded, || Temperature::from_celsius(0.)),
+            load_average: self
+                .load_average
+                .collect(resolution, padded, || LoadAverage::per_core(0., 0., 0.)),
+            cpu_pressure: self
+                .cpu_pressure
+                .collect_if_recorded(resolution, padded, idle_pressure),
+            memory_pressure: self.memory_pressure.collect_if_recorded(
+                resolution,
+                padded,
+                idle_pressure,
+            ),
+            io_pressure: self
+                .io_pressure
+                .collect_if_recorded(resolution, padded, idle_pressure),
+            filesystems: self.filesystem_usage(),
+            cgroups: self
+                .cgroups
+                .iter()
+                .map(|(name, history)| (name.clone(), history.build_metrics(resolution, padded)))
+                .collect(),
+            graph_samples,
+        }
+    }
+}
+
+impl InterfaceFilter {
+    pub fn new(include: &[Glob], exclude: &[Glob]) -> Result<Self, globset::Error> {
+        let include = match include {
+            [] => None,
+            globs => Some(build_glob_set(globs)?),
+        };
+        let exclude = build_glob_set(exclude)?;
+        Ok(Self { include, exclude })
+    }
+
+    #[must_use]
+    pub fn matches(&self, interface_name: &str) -> bool {
+        let included = self
+            .include
+            .as_ref()
+            .is_none_or(|include| include.is_match(interface_name));
+        included && !self.exclude.is_match(interface_name)
+    }
+}
+
+impl SensorFilter {
+    pub fn new(include: &[Glob]) -> Result<Self, globset::Error> {
+        let include = match include {
+            [] => None,
+            globs => Some(build_glob_set(globs)?),
+        };
+        Ok(Self { include })
+    }
+
+    #[must_use]
+    pub fn matches(&self, sensor_label: &str) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.is_match(sensor_label))
+    }
+}
+
+fn build_glob_set(globs: &[Glob]) -> Result<GlobSet, globset::Error> {
+    globs
+        .iter()
+        .cloned()
+        .fold(&mut GlobSetBuilder::new(), GlobSetBuilder::add)
+        .build()
+}
+
+impl<T: Aggregate> TieredHistory<T> {
+    /// Minute averages are kept for an hour.
+    const MINUTE_CAPACITY: usize = 60;
+    /// Hour averages are kept for a week.
+    const HOUR_CAPACITY: usize = 7 * 24;
+
+    fn new(realtime_capacity: usize) -> Self {
+        Self {
+            realtime: AllocRingBuffer::new(realtime_capacity),
+            minute: AllocRingBuffer::new(Self::MINUTE_CAPACITY),
+            hour: AllocRingBuffer::new(Self::HOUR_CAPACITY),
+            pending_minute: Vec::new(),
+            pending_hour: Vec::new(),
+        }
+    }
+
+    fn enqueue(&mut self, measurement: T) {
+        self.realtime.enqueue(measurement);
+        self.pending_minute.push(measurement);
+    }
+
+    /// Average the pending measurements of the tier below into `resolution`.
+    fn roll_up(&mut self, resolution: Resolution) {
+        match resolution {
+            Resolution::Realtime => {}
+            Resolution::Minute => {
+                if let Some(average) = T::aggregate(&self.pending_minute) {
+                    self.minute.enqueue(average);
+                    self.pending_hour.push(average);
+                }
+                self.pending_minute.clear();
+            }
+            Resolution::Hour => {
+                if let Some(average) = T::aggregate(&self.pending_hour) {
+                    self.hour.enqueue(average);
+                }
+                self.pending_hour.clear();
+            }
+        }
+    }
+
+    fn snapshot(&self) -> TieredSnapshot<T> {
+        TieredSnapshot {
+            realtime: self.realtime.to_vec(),
+            minute: self.minute.to_vec(),
+            hour: self.hour.to_vec(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: TieredSnapshot<T>, missed: MissedMeasurements) {
+        let tiers = [
+            (&mut self.realtime, snapshot.realtime, missed.realtime),
+            (&mut self.minute, snapshot.minute, missed.minute),
+            (&mut self.hour, snapshot.hour, missed.hour),
+        ];
+        for (buffer, restored, missed) in tiers {
+            let current = buffer.to_vec();
+            let kept = buffer.capacity().saturating_sub(missed).min(restored.len());
+            buffer.clear();
+            for measurement in restored[restored.len() - kept..].iter().chain(&current) {
+                buffer.enqueue(*measurement);
+            }
+        }
+    }
+
+    /// Like [`Self::collect`], but never pads a history that has never recorded
+    /// anything, e.g. of a sensor that the machine doesn't have. This way
+    /// clients can tell that there is nothing to show.
+    fn collect_if_recorded(
+        &self,
+        resolution: Resolution,
+        padded: bool,
+        padding: impl Fn() -> T,
+    ) -> Vec<T> {
+        let recorded = [&self.realtime, &self.minute, &self.hour]
+            .iter()
+            .any(|buffer| !buffer.is_empty());
+        self.collect(resolution, padded && recorded, padding)
+    }
+
+    /// Collect a tier newest-first, optionally padding it up to its capacity.
+    fn collect(&self, resolution: Resolution, padded: bool, padding: impl Fn() -> T) -> Vec<T> {
+        let buffer = match resolution {
+            Resolution::Realtime => &self.realtime,
+            Resolution::Minute => &self.minute,
+            Resolution::Hour => &self.hour,
+        };
+        buffer
             .iter()
             .copied()
             .rev()
-            .pad_using(self.net_usage_rate.capacity(), |_| NetUsageRate::idle())
+            .pad_using(if padded { buffer.capacity() } else { 0 }, |_| padding())
+            .collect()
+    }
+}
+
+impl DiskHistory {
+    /// A history that starts out idle.
+    fn new(capacity: usize) -> Self {
+        let mut history = Self::empty(capacity);
+        let zero = DiskIo::from_bytes(0);
+        history.enqueue(zero, zero, DiskIoRate::idle().duration());
+        history
+    }
+
+    fn empty(capacity: usize) -> Self {
+        Self {
+            combined: TieredHistory::new(capacity),
+            read: TieredHistory::new(capacity),
+            written: TieredHistory::new(capacity),
+        }
+    }
+
+    fn enqueue(&mut self, read: DiskIo, written: DiskIo, duration: Duration) {
+        let rate = |disk_io| DiskIoRate::from_io_and_duration(disk_io, duration);
+        self.combined.enqueue(rate(read + written));
+        self.read.enqueue(rate(read));
+        self.written.enqueue(rate(written));
+    }
+
+    fn roll_up(&mut self, resolution: Resolution) {
+        self.combined.roll_up(resolution);
+        self.read.roll_up(resolution);
+        self.written.roll_up(resolution);
+    }
+
+    fn snapshot(&self) -> DiskSnapshot {
+        DiskSnapshot {
+            combined: self.combined.snapshot(),
+            read: self.read.snapshot(),
+            written: self.written.snapshot(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: DiskSnapshot, missed: MissedMeasurements) {
+        self.combined.restore(snapshot.combined, missed);
+        self.read.restore(snapshot.read, missed);
+        self.written.restore(snapshot.written, missed);
+    }
+
+    fn build_metrics(&self, resolution: Resolution, padded: bool) -> DiskMetrics {
+        let collect = |history: &TieredHistory<DiskIoRate>| {
+            history.collect(resolution, padded, DiskIoRate::idle)
+        };
+        DiskMetrics {
+            disk_io_rate: collect(&self.combined),
+            disk_read_rate: collect(&self.read),
+            disk_write_rate: collect(&self.written),
+        }
+    }
+}
+
+impl CgroupHistory {
+    fn new(capacity: usize, directory: PathBuf) -> Self {
+        let counters = read_counters(&directory);
+        if counters.is_none() {
+            tracing::warn!(?directory, "No such cgroup (yet)");
+        }
+
+        // Nothing is recorded before the first refresh, which only serves as a
+        // baseline, so that all series stay aligned.
+        Self {
+            directory,
+            counters,
+            cpu_usage: TieredHistory::new(capacity),
+            mem_usage: TieredHistory::new(capacity),
+            disk_io: DiskHistory::empty(capacity),
+        }
+    }
+
+    /// Turn the counters accumulated over the last `elapsed` into rates.
+    ///
+    /// Nothing is recorded while the cgroup doesn't exist, and the first
+    /// refresh after it (re)appears only serves as a baseline.
+    #[expect(clippy::cast_possible_truncation)]
+    #[expect(clippy::cast_precision_loss)]
+    fn refresh(&mut self, elapsed: Duration, cores: usize, total_memory: u64) {
+        let counters = read_counters(&self.directory);
+        let previous = std::mem::replace(&mut self.counters, counters);
+        let (Some(previous), Some(counters)) = (previous, counters) else {
+            return;
+        };
+
+        let cpu_time = counters.cpu_time.saturating_sub(previous.cpu_time);
+        let available_time = elapsed.as_secs_f64() * cores.max(1) as f64;
+        let cpu_share = match available_time > 0. {
+            true => (cpu_time.as_secs_f64() / available_time * 100.) as f32,
+            false => 0.,
+        };
+        self.cpu_usage.enqueue(CpuUsage::from_percentage(cpu_share));
+
+        if let Some(memory) = counters.memory {
+            let limit = counters
+                .memory_limit
+                .map_or(total_memory, |limit| limit.min(total_memory));
+            self.mem_usage.enqueue(MemUsage::from_bytes(memory, limit));
+        }
+
+        let read = counters.read_bytes.saturating_sub(previous.read_bytes);
+        let written = counters
+            .written_bytes
+            .saturating_sub(previous.written_bytes);
+        self.disk_io.enqueue(
+            DiskIo::from_bytes(read),
+            DiskIo::from_bytes(written),
+            elapsed,
+        );
+    }
+
+    fn roll_up(&mut self, resolution: Resolution) {
+        self.cpu_usage.roll_up(resolution);
+        self.mem_usage.roll_up(resolution);
+        self.disk_io.roll_up(resolution);
+    }
+
+    fn snapshot(&self) -> CgroupSnapshot {
+        CgroupSnapshot {
+            cpu_usage: self.cpu_usage.snapshot(),
+            mem_usage: self.mem_usage.snapshot(),
+            disk_io: self.disk_io.snapshot(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: CgroupSnapshot, missed: MissedMeasurements) {
+        self.cpu_usage.restore(snapshot.cpu_usage, missed);
+        self.mem_usage.restore(snapshot.mem_usage, missed);
+        self.disk_io.restore(snapshot.disk_io, missed);
+    }
+
+    /// Memory usage is only padded if the memory controller is enabled.
+    fn build_metrics(&self, resolution: Resolution, padded: bool) -> CgroupMetrics {
+        CgroupMetrics {
+            cpu_usage: self
+                .cpu_usage
+                .collect(resolution, padded, || CpuUsage::from_percentage(0.)),
+            mem_usage: self
+                .mem_usage
+                .collect_if_recorded(resolution, padded, MemUsage::unused),
+            disk: self.disk_io.build_metrics(resolution, padded),
+        }
+    }
+}
+
+impl HistorySnapshot {
+    /// Turn the CPU and network usage of a snapshot written before they were
+    /// sampled by collectors into the series of those collectors.
+    fn migrate_legacy_series(&mut self, intervals: &UpdateIntervals) {
+        // CPU usage didn't record how long it was measured over, which was
+        // roughly the interval of its tier.
+        let cpu = |usage: CpuUsage, interval: Duration| SeriesSample {
+            value: usage.grading_value(),
+            seconds: interval.as_secs_f32(),
+        };
+        let net = |rate: NetUsageRate, _| SeriesSample {
+            value: rate.grading_value(),
+            seconds: rate.duration().as_secs_f32(),
+        };
+
+        let mut legacy_series = Vec::new();
+        if let Some(cpu_usage) = self.cpu_usage.take() {
+            legacy_series.push((CpuCollector::NAME.to_owned(), cpu_usage.map(intervals, cpu)));
+        }
+        for (core, core_usage) in std::mem::take(&mut self.cpu_core_usage)
+            .into_iter()
+            .enumerate()
+        {
+            let name = qualified_series(CpuCollector::CORES, &core.to_string());
+            legacy_series.push((name, core_usage.map(intervals, cpu)));
+        }
+
+        let interfaces = std::mem::take(&mut self.net_interface_usage)
+            .into_iter()
+            .map(|(interface, net_usage)| (Some(interface), net_usage));
+        for (interface, net_usage) in self
+            .net_usage
+            .take()
+            .map(|net_usage| (None, net_usage))
+            .into_iter()
+            .chain(interfaces)
+        {
+            let directions = [
+                (NetCollector::NAME, net_usage.combined),
+                (NetCollector::RECEIVED, net_usage.received),
+                (NetCollector::TRANSMITTED, net_usage.transmitted),
+            ];
+            for (series, rates) in directions {
+                let name = interface.as_ref().map_or_else(
+                    || series.to_owned(),
+                    |interface| qualified_series(series, interface),
+                );
+                legacy_series.push((name, rates.map(intervals, net)));
+            }
+        }
+
+        if !legacy_series.is_empty() {
+            tracing::info!("Migrating the CPU and network usage of an old snapshot");
+        }
+        for (name, series) in legacy_series {
+            self.series.entry(name).or_insert(series);
+        }
+    }
+}
+
+impl<T> TieredSnapshot<T> {
+    /// Convert every measurement, given the update interval of its tier.
+    fn map<U>(
+        self,
+        intervals: &UpdateIntervals,
+        convert: impl Fn(T, Duration) -> U,
+    ) -> TieredSnapshot<U> {
+        let tier = |measurements: Vec<T>, interval| {
+            measurements
+                .into_iter()
+                .map(|measurement| convert(measurement, interval))
+                .collect()
+        };
+        TieredSnapshot {
+            realtime: tier(self.realtime, intervals.realtime),
+            minute: tier(self.minute, intervals.minute),
+            hour: tier(self.hour, intervals.hour),
+        }
+    }
+}
+
+impl<T> Default for TieredSnapshot<T> {
+    fn default() -> Self {
+        Self {
+            realtime: Vec::new(),
+            minute: Vec::new(),
+            hour: Vec::new(),
+        }
+    }
+}
+
+impl UpdateIntervals {
+    /// How many measurements of every tier were missed since `taken_at`.
+    fn missed_since(&self, taken_at: SystemTime) -> MissedMeasurements {
+        let age = taken_at.elapsed().unwrap_or_default();
+        MissedMeasurements {
+            realtime: intervals_within(age, self.realtime),
+            minute: intervals_within(age, self.minute),
+            hour: intervals_within(age, self.hour),
+        }
+    }
+}
+
+/// How many whole `interval`s fit into `duration`.
+fn intervals_within(duration: Duration, interval: Duration) -> usize {
+    let intervals = duration.as_nanos() / interval.as_nanos().max(1);
+    usize::try_from(intervals).unwrap_or(usize::MAX)
+}
+
+impl Aggregate for CpuUsage {
+    #[expect(clippy::cast_precision_loss)]
+    fn aggregate(samples: &[Self]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let total: f32 = samples
+            .iter()
+            .map(|sample| sample.get::<si::ratio::percent>())
+            .sum();
+        Some(Self::from_percentage(total / samples.len() as f32))
+    }
+}
+
+impl Aggregate for f32 {
+    #[expect(clippy::cast_precision_loss)]
+    fn aggregate(samples: &[Self]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        Some(samples.iter().sum::<Self>() / samples.len() as Self)
+    }
+}
+
+impl SeriesSample {
+    const fn idle() -> Self {
+        Self {
+            value: 0.,
+            seconds: 0.,
+        }
+    }
+}
+
+impl Aggregate for SeriesSample {
+    /// Values are weighted by their duration, which is the exact average rate
+    /// over the whole period. Without any duration, e.g. for the very first
+    /// sample, they are weighted equally.
+    #[expect(clippy::cast_precision_loss)]
+    fn aggregate(samples: &[Self]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let seconds: f32 = samples.iter().map(|sample| sample.seconds).sum();
+        let value = if seconds > 0. {
+            samples
+                .iter()
+                .map(|sample| sample.value * sample.seconds)
+                .sum::<f32>()
+                / seconds
+        } else {
+            samples.iter().map(|sample| sample.value).sum::<f32>() / samples.len() as f32
+        };
+        Some(Self { value, seconds })
+    }
+}
+
+impl Aggregate for DiskIoRate {
+    /// Data is summed up over the combined duration, which is the exact average
+    /// rate over the whole period.
+    fn aggregate(samples: &[Self]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let disk_io = samples.iter().map(Self::disk_io).sum();
+        let duration = samples.iter().map(Self::duration).sum();
+        Some(Self::from_io_and_duration(disk_io, duration))
+    }
+}
+
+impl Aggregate for Temperature {
+    #[expect(clippy::cast_precision_loss)]
+    fn aggregate(samples: &[Self]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let total: f32 = samples
+            .iter()
+            .map(|sample| sample.get::<si::thermodynamic_temperature::degree_celsius>())
+            .sum();
+        Some(Self::from_celsius(total / samples.len() as f32))
+    }
+}
+
+impl Aggregate for LoadAverage {
+    #[expect(clippy::cast_precision_loss)]
+    fn aggregate(samples: &[Self]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let count = samples.len() as f32;
+        let mean = |window: fn(&Self) -> f32| samples.iter().map(window).sum::<f32>() / count;
+        Some(Self::per_core(
+            mean(Self::one),
+            mean(Self::five),
+            mean(Self::fifteen),
+        ))
+    }
+}
+
+impl Aggregate for Pressure {
+    #[expect(clippy::cast_precision_loss)]
+    fn aggregate(samples: &[Self]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let count = samples.len() as f32;
+        let mean = |share: fn(&Self) -> si::f32::Ratio| {
+            samples
+                .iter()
+                .map(|sample| share(sample).get::<si::ratio::percent>())
+                .sum::<f32>()
+                / count
+        };
+        Some(Self::from_percentages(mean(Self::some), mean(Self::full)))
+    }
+}
+
+impl Aggregate for MemUsage {
+    fn aggregate(samples: &[Self]) -> Option<Self> {
+        let count = u64::try_from(samples.len())
+            .ok()
+            .filter(|&count| count > 0)?;
+        let bytes = |information: si::u64::Information| information.get::<si::information::byte>();
+        let used: u64 = samples.iter().map(|sample| bytes(sample.used())).sum();
+        let total: u64 = samples.iter().map(|sample| bytes(sample.total())).sum();
+        Some(Self::from_bytes(used / count, total / count))
+    }
+}
+
+impl Series {
+    /// CPU usage in percent, graded like that of the [`CpuCollector`].
+    pub fn cpu_usage(values: Vec<f32>) -> Self {
+        Self {
+            unit: CpuUsage::UNIT.to_owned(),
+            thresholds: CpuUsageGrading::THRESHOLDS,
+            values,
+        }
+    }
+}
+
+impl Metrics {
+    /// Replace the system-wide network usage with that of a single interface.
+    ///
+    /// Returns [`None`] if the server does not know of such an interface.
+    #[must_use]
+    pub fn with_interface(mut self, interface_name: &str) -> Option<Self> {
+        let mut known = false;
+        for series in [
+            NetCollector::NAME,
+            NetCollector::RECEIVED,
+            NetCollector::TRANSMITTED,
+        ] {
+            let interface_series = qualified_series(series, interface_name);
+            if let Some(net_usage) = self.series.remove(&interface_series) {
+                self.series.insert(series.to_owned(), net_usage);
+                known = true;
+            }
+        }
+        known.then_some(self)
+    }
+
+    /// Replace the series of `measurement_type` with its series called `name`,
+    /// e.g. that of a single external command.
+    ///
+    /// Returns [`None`] if the server does not report such a series.
+    #[must_use]
+    pub fn with_named_series(
+        mut self,
+        measurement_type: MeasurementType,
+        name: &str,
+    ) -> Option<Self> {
+        let series = measurement_type.name();
+        let named_series = self.series.remove(&qualified_series(&series, name))?;
+        self.series.insert(series, named_series);
+        Some(self)
+    }
+
+    /// Replace the system-wide CPU, memory and disk usage with that of a single
+    /// cgroup.
+    ///
+    /// Returns [`None`] if the server does not account such a cgroup.
+    #[must_use]
+    pub fn with_cgroup(mut self, cgroup: &str) -> Option<Self> {
+        let cgroup_metrics = self.cgroups.remove(cgroup)?;
+        let cpu_usage = Series::cpu_usage(
+            cgroup_metrics
+                .cpu_usage
+                .iter()
+                .map(GradedMeasurement::grading_value)
+                .collect(),
+        );
+        self.series.insert(CpuCollector::NAME.to_owned(), cpu_usage);
+        self.mem_usage = cgroup_metrics.mem_usage;
+        self.disk = cgroup_metrics.disk;
+        Some(self)
+    }
+
+    /// Drop the space usage of every filesystem but the one mounted at
+    /// `mount_point`.
+    ///
+    /// Returns [`None`] if the server does not report such a filesystem.
+    #[must_use]
+    pub fn with_mount_point(mut self, mount_point: &str) -> Option<Self> {
+        let (mount_point, usage) = self.filesystems.remove_entry(mount_point)?;
+        self.filesystems = BTreeMap::from([(mount_point, usage)]);
+        Some(self)
+    }
+
+    /// Drop every series that isn't needed to render any of the given
+    /// measurement types.
+    pub fn retain(&mut self, measurement_types: &[MeasurementType]) {
+        let wanted = |candidates: &[MeasurementType]| {
+            candidates
+                .iter()
+                .any(|candidate| measurement_types.contains(candidate))
+        };
+
+        self.series
+            .retain(|name, _| match MeasurementType::of_series(name) {
+                Some(MeasurementType::CpuCores) => {
+                    wanted(&[MeasurementType::CpuCores, MeasurementType::CpuMax])
+                }
+                Some(measurement_type) => wanted(&[measurement_type]),
+                None => false,
+            });
+        if !wanted(&[MeasurementType::Mem]) {
+            self.mem_usage.clear();
+        }
+        if !wanted(&[MeasurementType::Swap]) {
+            self.swap_usage.clear();
+        }
+        if !wanted(&[MeasurementType::Fs]) {
+            self.filesystems.clear();
+        }
+        if !wanted(&[MeasurementType::Temp]) {
+            self.temperature.clear();
+        }
+        if !wanted(&[MeasurementType::Load]) {
+            self.load_average.clear();
+        }
+        if !wanted(&[MeasurementType::CpuPressure]) {
+            self.cpu_pressure.clear();
+        }
+        if !wanted(&[MeasurementType::MemPressure]) {
+            self.memory_pressure.clear();
+        }
+        if !wanted(&[MeasurementType::IoPressure]) {
+            self.io_pressure.clear();
+        }
+
+        self.disk.retain(measurement_types);
+
+        if !wanted(&MeasurementType::CGROUP_TYPES) {
+            self.cgroups.clear();
+        }
+        for cgroup_metrics in self.cgroups.values_mut() {
+            cgroup_metrics.retain(measurement_types);
+        }
+    }
+
+    /// Drop all but the newest `samples` measurements of every series.
+    pub fn truncate(&mut self, samples: usize) {
+        for series in self.series.values_mut() {
+            series.values.truncate(samples);
+        }
+        self.mem_usage.truncate(samples);
+        self.swap_usage.truncate(samples);
+        self.temperature.truncate(samples);
+        self.load_average.truncate(samples);
+        self.cpu_pressure.truncate(samples);
+        self.memory_pressure.truncate(samples);
+        self.io_pressure.truncate(samples);
+        self.disk.truncate(samples);
+        for cgroup_metrics in self.cgroups.values_mut() {
+            cgroup_metrics.truncate(samples);
+        }
+        self.graph_samples = self.graph_samples.min(samples);
+    }
+
+    /// The series of every logical CPU core, in order.
+    pub fn cpu_core_usage(&self) -> impl Iterator<Item = &Series> {
+        (0..).map_while(|core: usize| {
+            let series = qualified_series(CpuCollector::CORES, &core.to_string());
+            self.series.get(&series)
+        })
+    }
+
+    /// Collapse the per-core CPU usage into a single series by picking the
+    /// busiest core at each point in time.
+    #[must_use]
+    pub fn max_cpu_core_usage(&self) -> Option<Series> {
+        let mut cores = self.cpu_core_usage().peekable();
+        let first_core = (*cores.peek()?).clone();
+        let series_length = self
+            .cpu_core_usage()
+            .map(|core| core.values.len())
+            .max()
+            .unwrap_or(0);
+        let values = (0..series_length)
+            .map(|index| {
+                self.cpu_core_usage()
+                    .filter_map(|core_usage| core_usage.values.get(index).copied())
+                    .max_by(f32::total_cmp)
+                    .unwrap_or(0.)
+            })
             .collect();
+        Some(Series {
+            values,
+            ..first_core
+        })
+    }
+}
 
-        Metrics {
-            cpu_usage,
-            net_usage_rate,
+impl CgroupMetrics {
+    fn retain(&mut self, measurement_types: &[MeasurementType]) {
+        if !measurement_types.contains(&MeasurementType::Cpu) {
+            self.cpu_usage.clear();
+        }
+        if !measurement_types.contains(&MeasurementType::Mem) {
+            self.mem_usage.clear();
         }
+        self.disk.retain(measurement_types);
+    }
+
+    fn truncate(&mut self, samples: usize) {
+        self.cpu_usage.truncate(samples);
+        self.mem_usage.truncate(samples);
+        self.disk.truncate(samples);
     }
 }
 
+impl DiskMetrics {
+    fn retain(&mut self, measurement_types: &[MeasurementType]) {
+        if !measurement_types.contains(&MeasurementType::Disk) {
+            self.disk_io_rate.clear();
+        }
+        if !measurement_types.contains(&MeasurementType::DiskRead) {
+            self.disk_read_rate.clear();
+        }
+        if !measurement_types.contains(&MeasurementType::DiskWrite) {
+            self.disk_write_rate.clear();
+        }
+    }
+
+    fn truncate(&mut self, samples: usize) {
+        self.disk_io_rate.truncate(samples);
+        self.disk_read_rate.truncate(samples);
+        self.disk_write_rate.truncate(samples);
+    }
+}
+
+/// Periodically refresh the realtime measurements of the [`ResourceMonitor`].
+///
+/// Every refresh is announced through `update_notifier`, so that streaming
+/// endpoints know when there are new measurements.
 #[tracing::instrument(skip_all)]
 pub async fn realtime_update_thread(
     resource_monitor: Arc<AsyncMutex<ResourceMonitor>>,
+    update_notifier: watch::Sender<()>,
     cancellation_token: CancellationToken,
 ) {
     let update_interval = resource_monitor.lock().await.update_intervals.realtime;
@@ -130,6 +1934,7 @@ pub async fn realtime_update_thread(
         loop {
             tokio::time::sleep(update_interval).await;
             resource_monitor.lock().await.refresh_realtime();
+            update_notifier.send_replace(());
         }
     };
 
@@ -138,3 +1943,505 @@ pub async fn realtime_update_thread(
         () = cancellation_token.cancelled() => {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use globset::Glob;
+    use serde_json::json;
+
+    use super::{
+        CgroupHistory, CgroupMetrics, CustomMeasurement, CustomMeasurementError, DiskMetrics,
+        HistorySnapshot, InterfaceFilter, MeasurementSelector, MeasurementType, Metrics,
+        MissedMeasurements, Resolution, ResourceMonitor, SensorFilter, Series, SeriesSample,
+        TieredHistory, TieredSnapshot, UpdateIntervals,
+    };
+    use crate::collector::{Collector, MAX_SERIES_NAME_LENGTH, SampleContext};
+    use crate::grading::{GradedMeasurement, Thresholds};
+    use crate::units::{CpuUsage, DiskIoRate, MemUsage, NetUsage, NetUsageRate, Temperature};
+
+    fn globs(patterns: &[&str]) -> Vec<Glob> {
+        patterns.iter().map(|p| Glob::new(p).unwrap()).collect()
+    }
+
+    #[test]
+    fn interface_filter() {
+        let everything = InterfaceFilter::default();
+        assert!(everything.matches("lo"));
+        assert!(everything.matches("enp3s0"));
+
+        let filter = InterfaceFilter::new(&globs(&["en*", "wl*"]), &globs(&["enp4s0"])).unwrap();
+        assert!(filter.matches("enp3s0"));
+        assert!(filter.matches("wlan0"));
+        assert!(!filter.matches("enp4s0"));
+        assert!(!filter.matches("lo"));
+        assert!(!filter.matches("docker0"));
+
+        let filter = InterfaceFilter::new(&[], &globs(&["lo", "docker*", "br-*"])).unwrap();
+        assert!(filter.matches("enp3s0"));
+        assert!(!filter.matches("lo"));
+        assert!(!filter.matches("docker0"));
+        assert!(!filter.matches("br-1a2b3c"));
+    }
+
+    #[test]
+    fn sensor_filter() {
+        assert!(SensorFilter::default().matches("acpitz temp1"));
+
+        let filter = SensorFilter::new(&globs(&["Package id *", "Tctl"])).unwrap();
+        assert!(filter.matches("Package id 0"));
+        assert!(filter.matches("Tctl"));
+        assert!(!filter.matches("nvme Composite"));
+    }
+
+    #[test]
+    fn measurement_selector_parsing() {
+        let cpu: MeasurementSelector = "cpu".parse().unwrap();
+        assert_eq!(cpu, MeasurementType::Cpu.into());
+        assert_eq!(cpu.to_string(), "cpu");
+
+        let queue: MeasurementSelector = "command:queue".parse().unwrap();
+        assert_eq!(queue.measurement_type, MeasurementType::Command);
+        assert_eq!(queue.series.as_deref(), Some("queue"));
+        assert_eq!(queue.to_string(), "command:queue");
+
+        let builds: MeasurementSelector = "custom:builds".parse().unwrap();
+        assert_eq!(builds.measurement_type, MeasurementType::Custom);
+        assert_eq!(builds.series.as_deref(), Some("builds"));
+
+        assert!("command".parse::<MeasurementSelector>().is_err());
+        assert!("cpu:0".parse::<MeasurementSelector>().is_err());
+        assert!("gpu".parse::<MeasurementSelector>().is_err());
+    }
+
+    #[test]
+    fn cgroup_history_refresh() {
+        let directory = std::env::temp_dir().join(format!("sulphur-cgroup-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let write_counters = |cpu_usec: u64, memory: u64, read: u64, written: u64| {
+            let write = |file: &str, contents: String| {
+                std::fs::write(directory.join(file), contents).unwrap();
+            };
+            write("cpu.stat", format!("usage_usec {cpu_usec}\n"));
+            write("memory.current", format!("{memory}\n"));
+            write("memory.max", String::from("1073741824\n"));
+            write("io.stat", format!("8:0 rbytes={read} wbytes={written}\n"));
+        };
+        let grading_values = |values: &[f32]| values.iter().map(|v| v.round()).collect::<Vec<_>>();
+
+        write_counters(1_000_000, 0, 0, 0);
+        let mut history = CgroupHistory::new(4, directory.clone());
+        let metrics = history.build_metrics(Resolution::Realtime, false);
+        assert!(metrics.cpu_usage.is_empty());
+        assert!(metrics.disk.disk_io_rate.is_empty());
+
+        // One second of CPU time within two seconds on two cores.
+        write_counters(2_000_000, 512 << 20, 2_000_000, 6_000_000);
+        history.refresh(Duration::from_secs(2), 2, 4 << 30);
+
+        // The counters were reset, e.g. because the cgroup was recreated.
+        write_counters(500_000, 256 << 20, 10, 10);
+        history.refresh(Duration::from_secs(2), 2, 4 << 30);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let metrics = history.build_metrics(Resolution::Realtime, false);
+        let percentages: Vec<f32> = metrics
+            .cpu_usage
+            .iter()
+            .map(GradedMeasurement::grading_value)
+            .collect();
+        assert_eq!(grading_values(&percentages), [0., 25.]);
+        let percentages: Vec<f32> = metrics
+            .mem_usage
+            .iter()
+            .map(GradedMeasurement::grading_value)
+            .collect();
+        assert_eq!(grading_values(&percentages), [25., 50.]);
+        let megabytes_per_second: Vec<f32> = metrics
+            .disk
+            .disk_write_rate
+            .iter()
+            .map(GradedMeasurement::grading_value)
+            .collect();
+        assert_eq!(grading_values(&megabytes_per_second), [0., 3.]);
+        assert_eq!(metrics.disk.disk_read_rate.len(), 2);
+    }
+
+    #[test]
+    fn metrics_selection() {
+        let names = [
+            "cpu",
+            "cpu-cores/0",
+            "cpu-cores/1",
+            "net",
+            "net-rx/eth0",
+            "command/queue",
+        ];
+        let cgroup_metrics = CgroupMetrics {
+            cpu_usage: vec![CpuUsage::from_percentage(1.); 3],
+            mem_usage: vec![MemUsage::unused(); 3],
+            disk: DiskMetrics::default(),
+        };
+        let mut metrics = Metrics {
+            series: names
+                .into_iter()
+                .map(|name| (name.to_owned(), Series::cpu_usage(vec![1., 2., 3.])))
+                .collect(),
+            mem_usage: vec![MemUsage::unused(); 3],
+            temperature: vec![Temperature::from_celsius(40.); 3],
+            disk: DiskMetrics {
+                disk_read_rate: vec![DiskIoRate::idle(); 3],
+                disk_write_rate: vec![DiskIoRate::idle(); 3],
+                ..DiskMetrics::default()
+            },
+            cgroups: BTreeMap::from([(String::from("system.slice"), cgroup_metrics)]),
+            graph_samples: 3,
+            ..Metrics::default()
+        };
+
+        metrics.retain(&[
+            MeasurementType::CpuMax,
+            MeasurementType::Mem,
+            MeasurementType::DiskRead,
+            MeasurementType::Command,
+        ]);
+        assert_eq!(
+            metrics.series.keys().collect::<Vec<_>>(),
+            ["command/queue", "cpu-cores/0", "cpu-cores/1"]
+        );
+        assert_eq!(metrics.mem_usage.len(), 3);
+        assert!(metrics.temperature.is_empty());
+        assert_eq!(metrics.disk.disk_read_rate.len(), 3);
+        assert!(metrics.disk.disk_write_rate.is_empty());
+        let cgroup_metrics = &metrics.cgroups["system.slice"];
+        assert!(cgroup_metrics.cpu_usage.is_empty());
+        assert_eq!(cgroup_metrics.mem_usage.len(), 3);
+
+        metrics.truncate(2);
+        assert!(
+            metrics
+                .series
+                .values()
+                .all(|series| series.values == [1., 2.])
+        );
+        assert_eq!(metrics.mem_usage.len(), 2);
+        assert_eq!(metrics.disk.disk_read_rate.len(), 2);
+        assert_eq!(metrics.cgroups["system.slice"].mem_usage.len(), 2);
+        assert_eq!(metrics.graph_samples, 2);
+    }
+
+    #[test]
+    fn max_cpu_core_usage() {
+        let mut metrics = Metrics::default();
+        assert!(metrics.max_cpu_core_usage().is_none());
+
+        // A core that is only reported for a while, e.g. after being brought
+        // online, doesn't count for the older measurements. Cores are numbered
+        // from zero, so those after a gap are left out.
+        metrics.series = BTreeMap::from([
+            (
+                String::from("cpu-cores/0"),
+                Series::cpu_usage(vec![10., 80., 30.]),
+            ),
+            (
+                String::from("cpu-cores/1"),
+                Series::cpu_usage(vec![50., 20.]),
+            ),
+            (String::from("cpu-cores/3"), Series::cpu_usage(vec![100.])),
+        ]);
+        let max_usage = metrics.max_cpu_core_usage().unwrap();
+        assert_eq!(max_usage.unit, "%");
+        assert_eq!(max_usage.values, [50., 80., 30.]);
+    }
+
+    #[test]
+    fn tiered_history_roll_up() {
+        let mut history = TieredHistory::new(4);
+        for percentage in [10., 20., 30.] {
+            history.enqueue(CpuUsage::from_percentage(percentage));
+        }
+        history.roll_up(Resolution::Minute);
+        history.enqueue(CpuUsage::from_percentage(60.));
+        history.roll_up(Resolution::Minute);
+        history.roll_up(Resolution::Hour);
+
+        let idle = || CpuUsage::from_percentage(0.);
+        let percentages = |resolution| {
+            history
+                .collect(resolution, true, idle)
+                .iter()
+                .take(3)
+                .map(|usage| usage.get::<uom::si::ratio::percent>().round())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(percentages(Resolution::Realtime), [60., 30., 20.]);
+        assert_eq!(percentages(Resolution::Minute), [60., 20., 0.]);
+        assert_eq!(percentages(Resolution::Hour), [40., 0., 0.]);
+        assert_eq!(history.collect(Resolution::Hour, true, idle).len(), 7 * 24);
+    }
+
+    #[test]
+    fn series_roll_up() {
+        let sample = |value, seconds| SeriesSample { value, seconds };
+        let mut history = TieredHistory::new(4);
+        for (value, seconds) in [(10., 1.), (40., 2.)] {
+            history.enqueue(sample(value, seconds));
+        }
+        history.roll_up(Resolution::Minute);
+        // The first sample of a series isn't measured over any time.
+        history.enqueue(sample(20., 0.));
+        history.roll_up(Resolution::Minute);
+        history.roll_up(Resolution::Hour);
+
+        let minute = history.collect(Resolution::Minute, false, SeriesSample::idle);
+        assert_eq!(minute, [sample(20., 0.), sample(30., 3.)]);
+        let hour = history.collect(Resolution::Hour, false, SeriesSample::idle);
+        assert_eq!(hour, [sample(30., 3.)]);
+    }
+
+    fn resource_monitor() -> ResourceMonitor {
+        let intervals = UpdateIntervals {
+            // Long enough that restored measurements are never missed.
+            realtime: Duration::from_secs(60),
+            minute: Duration::from_secs(60),
+            hour: Duration::from_secs(60 * 60),
+        };
+        ResourceMonitor::new(
+            4,
+            intervals,
+            InterfaceFilter::default(),
+            Vec::new(),
+            SensorFilter::default(),
+            false,
+            &[],
+        )
+    }
+
+    #[test]
+    fn custom_series() {
+        let measurement = |value, unit: Option<&str>, thresholds| CustomMeasurement {
+            value,
+            unit: unit.map(String::from),
+            thresholds,
+        };
+        let mut monitor = resource_monitor();
+
+        assert!(matches!(
+            monitor.push_custom("builds", measurement(1., None, None)),
+            Err(CustomMeasurementError::MissingThresholds)
+        ));
+        let thresholds = Thresholds::new(1., 2., 3.).unwrap();
+        assert!(matches!(
+            monitor.push_custom("builds", measurement(f32::NAN, None, Some(thresholds))),
+            Err(CustomMeasurementError::NotFinite)
+        ));
+        for name in ["", "a/b", "a:b", &"a".repeat(MAX_SERIES_NAME_LENGTH + 1)] {
+            assert!(matches!(
+                monitor.push_custom(name, measurement(1., None, Some(thresholds))),
+                Err(CustomMeasurementError::InvalidName)
+            ));
+        }
+
+        monitor
+            .push_custom("builds", measurement(1., Some(" jobs"), Some(thresholds)))
+            .unwrap();
+        let updated = Thresholds::new(10., 20., 30.).unwrap();
+        monitor
+            .push_custom("builds", measurement(2., None, Some(updated)))
+            .unwrap();
+        monitor
+            .push_custom("builds", measurement(3., Some(" builds"), None))
+            .unwrap();
+        // Only the newest value is recorded, once per update.
+        monitor.refresh_realtime();
+        monitor
+            .push_custom("builds", measurement(4., None, None))
+            .unwrap();
+        monitor.refresh_realtime();
+        let metrics = monitor.build_recorded_metrics(Resolution::Realtime);
+        let series = &metrics.series["custom/builds"];
+        assert_eq!(series.unit, " builds");
+        assert_eq!(series.thresholds, updated);
+        assert_eq!(series.values, [4., 3.]);
+
+        // A restored series keeps its history, but isn't recorded until it is
+        // pushed to again.
+        let mut restored = resource_monitor();
+        restored.restore(monitor.snapshot());
+        restored.refresh_realtime();
+        let metrics = restored.build_recorded_metrics(Resolution::Realtime);
+        assert_eq!(metrics.series["custom/builds"], *series);
+
+        for i in 1..ResourceMonitor::MAX_CUSTOM_SERIES {
+            monitor
+                .push_custom(
+                    &format!("series-{i}"),
+                    measurement(1., None, Some(thresholds)),
+                )
+                .unwrap();
+        }
+        assert!(matches!(
+            monitor.push_custom("one-too-many", measurement(1., None, Some(thresholds))),
+            Err(CustomMeasurementError::TooManySeries)
+        ));
+        monitor
+            .push_custom("builds", measurement(5., None, None))
+            .unwrap();
+
+        monitor.refresh_realtime();
+        let metrics = monitor.build_recorded_metrics(Resolution::Realtime);
+        assert!(metrics.series.contains_key("custom/builds"));
+        monitor.set_custom_idle_time(Duration::ZERO);
+        monitor.refresh_realtime();
+        let metrics = monitor.build_recorded_metrics(Resolution::Realtime);
+        assert!(
+            !metrics
+                .series
+                .keys()
+                .any(|name| name.starts_with("custom/"))
+        );
+    }
+
+    #[test]
+    fn tiered_history_restore() {
+        let mut previous = TieredHistory::new(4);
+        for percentage in [10., 20., 30.] {
+            previous.enqueue(CpuUsage::from_percentage(percentage));
+            previous.roll_up(Resolution::Minute);
+        }
+
+        let mut history = TieredHistory::new(4);
+        history.enqueue(CpuUsage::from_percentage(50.));
+        let missed = MissedMeasurements {
+            realtime: 3,
+            minute: 0,
+            hour: 0,
+        };
+        history.restore(previous.snapshot(), missed);
+
+        let percentages = |resolution| {
+            history
+                .collect(resolution, true, || CpuUsage::from_percentage(0.))
+                .iter()
+                .take(4)
+                .map(|usage| usage.get::<uom::si::ratio::percent>().round())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(percentages(Resolution::Realtime), [50., 30., 0., 0.]);
+        assert_eq!(percentages(Resolution::Minute), [30., 20., 10., 0.]);
+    }
+
+    #[test]
+    fn legacy_snapshot_migration() {
+        fn tiered<T>(realtime: Vec<T>) -> TieredSnapshot<T> {
+            TieredSnapshot {
+                realtime,
+                minute: Vec::new(),
+                hour: Vec::new(),
+            }
+        }
+
+        let monitor = resource_monitor();
+        let cpu_usage = serde_json::to_value(tiered(vec![CpuUsage::from_percentage(50.)])).unwrap();
+        // 8 Mbit over 8 seconds.
+        let rate = NetUsageRate::from_usage_and_duration(
+            NetUsage::from_bytes(1_000_000),
+            Duration::from_secs(8),
+        );
+        let rates = serde_json::to_value(tiered(vec![rate])).unwrap();
+        let net_usage = json!({ "combined": rates, "received": rates, "transmitted": rates });
+
+        let mut snapshot = serde_json::to_value(monitor.snapshot()).unwrap();
+        snapshot["series"] = json!({});
+        snapshot["cpu_usage"] = cpu_usage.clone();
+        snapshot["cpu_core_usage"] = json!([cpu_usage]);
+        snapshot["net_usage"] = net_usage.clone();
+        snapshot["net_interface_usage"] = json!({ "eth0": net_usage });
+        let mut snapshot: HistorySnapshot = serde_json::from_value(snapshot).unwrap();
+        snapshot.migrate_legacy_series(&monitor.update_intervals);
+
+        assert_eq!(
+            snapshot.series.keys().collect::<Vec<_>>(),
+            [
+                "cpu",
+                "cpu-cores/0",
+                "net",
+                "net-rx",
+                "net-rx/eth0",
+                "net-tx",
+                "net-tx/eth0",
+                "net/eth0"
+            ]
+        );
+        let realtime = |name: &str| {
+            let sample = snapshot.series[name].realtime[0];
+            (sample.value.round(), sample.seconds.round())
+        };
+        assert_eq!(realtime("cpu-cores/0"), (50., 60.));
+        assert_eq!(realtime("net-tx/eth0"), (1., 8.));
+    }
+
+    /// Reports the next of `values` on every sample, nothing once they run
+    /// out, and nothing for the first `skipped` samples, like a command that
+    /// hasn't printed a number yet.
+    #[derive(Debug)]
+    struct ScriptedCollector {
+        skipped: usize,
+        values: Vec<f32>,
+    }
+
+    impl Collector for ScriptedCollector {
+        fn unit(&self) -> &'static str {
+            ""
+        }
+
+        fn default_thresholds(&self) -> Thresholds {
+            Thresholds::new(1., 2., 3.).unwrap()
+        }
+
+        fn sample(&mut self, _context: &SampleContext) -> Vec<(String, f32)> {
+            if self.skipped > 0 {
+                self.skipped -= 1;
+                return Vec::new();
+            }
+            self.values
+                .pop()
+                .map(|value| (String::from("command/queue"), value))
+                .into_iter()
+                .collect()
+        }
+    }
+
+    #[test]
+    fn late_series_restore() {
+        let values = |monitor: &ResourceMonitor| {
+            monitor
+                .build_recorded_metrics(Resolution::Realtime)
+                .series
+                .get("command/queue")
+                .map(|series| series.values.clone())
+        };
+
+        let mut previous = resource_monitor();
+        previous.add_collector(Box::new(ScriptedCollector {
+            skipped: 0,
+            values: vec![2., 1.],
+        }));
+        previous.refresh_realtime();
+        previous.refresh_realtime();
+        assert_eq!(values(&previous), Some(vec![2., 1.]));
+
+        let mut monitor = resource_monitor();
+        monitor.add_collector(Box::new(ScriptedCollector {
+            skipped: 1,
+            values: vec![3.],
+        }));
+        monitor.restore(previous.snapshot());
+        monitor.refresh_realtime();
+        assert_eq!(values(&monitor), None);
+        monitor.refresh_realtime();
+        assert_eq!(values(&monitor), Some(vec![3., 2., 1.]));
+    }
+}
@@ -1,18 +1,35 @@
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
-use axum::extract::State;
+use axum::body::Body;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
 use axum::{Json, Router, routing};
 use clap::Parser;
 use epicentre_diagnostics::color_eyre::eyre::{self, Context};
 use epicentre_diagnostics::tracing;
-use tokio::sync::Mutex as AsyncMutex;
+use globset::Glob;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex as AsyncMutex, watch};
 use tokio_util::sync::CancellationToken;
 
+use crate::command::CommandCollector;
 use crate::graph::GRAPH_DENSITY;
-use crate::resource_monitor::{Metrics, ResourceMonitor, UpdateIntervals, realtime_update_thread};
-use crate::{CLAP_STYLE, DEFAULT_API_ADDRESS};
+use crate::processes::{ProcessSort, ProcessUsage};
+use crate::resource_monitor::{
+    CustomMeasurement, CustomMeasurementError, InterfaceFilter, MeasurementType, Metrics,
+    Resolution, ResourceMonitor, SensorFilter, UpdateIntervals, realtime_update_thread,
+};
+use crate::summary::Summary;
+use crate::{
+    CLAP_STYLE, DEFAULT_API_ADDRESS, METRICS_CUSTOM_ENDPOINT, METRICS_ENDPOINT,
+    METRICS_STREAM_ENDPOINT, METRICS_SUMMARY_ENDPOINT, METRICS_WEBSOCKET_ENDPOINT,
+    PROCESSES_TOP_ENDPOINT, PROMETHEUS_ENDPOINT, persistence,
+};
 
 #[derive(Parser, Debug)]
 #[must_use]
@@ -35,6 +52,110 @@ pub struct Options {
     /// of the produced graph in seconds, whatever makes more sense to you.
     #[arg(short('s'), long, default_value_t = 5.0)]
     pub span_seconds: f64,
+
+    /// How often, in seconds, realtime measurements are averaged into the
+    /// `minute` resolution, which keeps the newest 60 averages.
+    #[arg(long, default_value_t = 60., value_parser = positive_seconds)]
+    pub minute_seconds: f64,
+
+    /// How often, in seconds, minute averages are averaged into the `hour`
+    /// resolution, which keeps the newest 168 averages.
+    #[arg(long, default_value_t = 3600., value_parser = positive_seconds)]
+    pub hour_seconds: f64,
+
+    /// Only take network interfaces matching this glob into account.
+    ///
+    /// May be specified multiple times. If omitted, all interfaces are
+    /// considered (unless excluded with `--exclude-interface`).
+    #[arg(short('i'), long("include-interface"), value_name = "GLOB")]
+    pub include_interfaces: Vec<Glob>,
+
+    /// Ignore network interfaces matching this glob, e.g. `lo` or `docker*`.
+    ///
+    /// May be specified multiple times. Takes precedence over
+    /// `--include-interface`.
+    #[arg(short('x'), long("exclude-interface"), value_name = "GLOB")]
+    pub exclude_interfaces: Vec<Glob>,
+
+    /// Only report space usage of the filesystem mounted here, e.g. `/` or
+    /// `/nix`.
+    ///
+    /// May be specified multiple times. If omitted, all filesystems are
+    /// reported.
+    #[arg(short('m'), long("mount-point"), value_name = "PATH")]
+    pub mount_points: Vec<PathBuf>,
+
+    /// Only take temperature sensors whose label matches this glob into
+    /// account, e.g. `Package id *` or `Tctl`.
+    ///
+    /// May be specified multiple times. If omitted, the hottest of all sensors
+    /// is reported. If no sensor matches, the available ones are logged and
+    /// the temperature is rendered as `n/a`.
+    #[arg(long("sensor"), value_name = "GLOB")]
+    pub sensors: Vec<Glob>,
+
+    /// Where to keep the history across restarts.
+    ///
+    /// Defaults to `$XDG_STATE_HOME/sulphur/history.json`.
+    #[arg(long, value_name = "PATH")]
+    pub history_file: Option<PathBuf>,
+
+    /// Start with an empty history every time, and never write it to disk.
+    #[arg(long, conflicts_with = "history_file")]
+    pub no_history_file: bool,
+
+    /// How often, in seconds, to write the history to disk.
+    ///
+    /// It is also written once more when the server is stopped.
+    #[arg(long, default_value_t = 60.0, value_parser = positive_seconds)]
+    pub snapshot_seconds: f64,
+
+    /// Keep track of the CPU and memory usage of every process, to serve the
+    /// heaviest ones on `/processes/top`.
+    ///
+    /// Off by default, since refreshing all processes is far more costly than
+    /// refreshing anything else.
+    #[arg(long)]
+    pub track_processes: bool,
+
+    /// Account the CPU, memory and disk usage of this cgroup (v2), e.g.
+    /// `system.slice` or `system.slice/nginx.service`.
+    ///
+    /// Relative paths are taken relative to `/sys/fs/cgroup`. May be specified
+    /// multiple times.
+    #[arg(long("cgroup"), value_name = "PATH")]
+    pub cgroups: Vec<PathBuf>,
+
+    /// Run the external commands configured in this TOML file, and graph the
+    /// number each of them prints.
+    ///
+    /// Every `[[command]]` table needs a `name`, the `command` to run with
+    /// `sh -c` and the `thresholds` to grade its numbers against, e.g.
+    /// `[10, 100, 1000]`. It may also set how often to run the command with
+    /// `interval_seconds` (10 by default) and a `unit`, e.g. `" jobs"`. A
+    /// command that fails three times in a row isn't graphed until it
+    /// succeeds again.
+    #[arg(long, value_name = "PATH")]
+    pub commands: Option<PathBuf>,
+
+    /// For how long, in seconds, to keep a series pushed to
+    /// `/metrics/custom/NAME` after its latest push.
+    #[arg(
+        long,
+        default_value_t = ResourceMonitor::DEFAULT_CUSTOM_IDLE_TIME.as_secs_f64(),
+        value_parser = positive_seconds
+    )]
+    pub custom_idle_seconds: f64,
+}
+
+/// Parse a positive number of seconds, which [`Duration::from_secs_f64`] can
+/// take without panicking.
+fn positive_seconds(value: &str) -> Result<f64, String> {
+    let seconds: f64 = value.parse().map_err(|error| format!("{error}"))?;
+    match Duration::try_from_secs_f64(seconds) {
+        Ok(duration) if !duration.is_zero() => Ok(seconds),
+        _ => Err(String::from("expected a positive number of seconds")),
+    }
 }
 
 #[tracing::instrument(name = "main")]
@@ -42,22 +163,88 @@ pub async fn run(options: &Options) -> Result<(), eyre::Error> {
     let measurement_capacity = options.graph_length * GRAPH_DENSITY;
     let update_intervals = UpdateIntervals {
         realtime: Duration::from_secs_f64(options.span_seconds / f64::from(measurement_capacity)),
+        minute: Duration::from_secs_f64(options.minute_seconds),
+        hour: Duration::from_secs_f64(options.hour_seconds),
     };
+    eyre::ensure!(
+        update_intervals.realtime <= update_intervals.minute
+            && update_intervals.minute <= update_intervals.hour,
+        "Every resolution must be at least as coarse as the previous one, got {update_intervals:?}"
+    );
 
     tracing::debug!(measurement_capacity, ?update_intervals);
 
-    let resource_monitor = ResourceMonitor::new(measurement_capacity.into(), update_intervals);
+    let interface_filter =
+        InterfaceFilter::new(&options.include_interfaces, &options.exclude_interfaces)
+            .wrap_err("Failed to build the network interface filter")?;
+    let sensor_filter = SensorFilter::new(&options.sensors)
+        .wrap_err("Failed to build the temperature sensor filter")?;
+
+    let mut resource_monitor = ResourceMonitor::new(
+        measurement_capacity.into(),
+        update_intervals,
+        interface_filter,
+        options.mount_points.clone(),
+        sensor_filter,
+        options.track_processes,
+        &options.cgroups,
+    );
+    resource_monitor.set_custom_idle_time(Duration::from_secs_f64(options.custom_idle_seconds));
+
+    let commands = match &options.commands {
+        Some(path) => crate::command::load(path)?,
+        None => Vec::new(),
+    };
+    let mut command_runners = Vec::new();
+    for command in commands {
+        let (collector, runner) = CommandCollector::new(command)?;
+        resource_monitor.add_collector(Box::new(collector));
+        command_runners.push(runner);
+    }
+
+    let history_path = match options.no_history_file {
+        true => None,
+        false => options
+            .history_file
+            .clone()
+            .or_else(persistence::default_history_path),
+    };
+    if let Some(history_path) = &history_path {
+        match persistence::load(history_path) {
+            Ok(Some(snapshot)) => resource_monitor.restore(snapshot),
+            Ok(None) => tracing::info!(?history_path, "No history to restore"),
+            Err(error) => tracing::warn!(?error, "Failed to restore the history"),
+        }
+    }
+
     let resource_monitor = Arc::new(AsyncMutex::new(resource_monitor));
+    let (update_notifier, _) = watch::channel(());
     let stop_signal = CancellationToken::new();
 
+    let snapshot_task = history_path.map(|history_path| {
+        tokio::spawn(persistence::snapshot_thread(
+            Arc::clone(&resource_monitor),
+            history_path,
+            Duration::from_secs_f64(options.snapshot_seconds),
+            stop_signal.child_token(),
+        ))
+    });
+
     tokio::select! {
         () = realtime_update_thread(
             Arc::clone(&resource_monitor),
+            update_notifier.clone(),
+            stop_signal.child_token(),
+        ) => { /* never fails & returns nothing */ }
+
+        () = crate::command::command_thread(
+            command_runners,
             stop_signal.child_token(),
         ) => { /* never fails & returns nothing */ }
 
         axum_result = axum_thread(
-            resource_monitor,
+            Arc::clone(&resource_monitor),
+            update_notifier,
             stop_signal.child_token(),
             options.api_address
         ) => axum_result.wrap_err("The axum thread returned an error")?,
@@ -70,19 +257,49 @@ pub async fn run(options: &Options) -> Result<(), eyre::Error> {
 
     stop_signal.cancel();
 
+    // The snapshot task saves the history a final time once stopped.
+    if let Some(snapshot_task) = snapshot_task {
+        snapshot_task
+            .await
+            .wrap_err("Failed to wait for the history to be saved")?
+            .wrap_err("Failed to save the history")?;
+    }
+
     Ok(())
 }
 
 #[tracing::instrument(skip_all)]
 pub async fn axum_thread(
     resource_monitor: Arc<AsyncMutex<ResourceMonitor>>,
+    update_notifier: watch::Sender<()>,
     cancellation_token: CancellationToken,
     socket_addr: SocketAddr,
 ) -> Result<(), std::io::Error> {
     let axum_future = async move {
-        let state = AxumState { resource_monitor };
+        let state = AxumState {
+            resource_monitor,
+            update_notifier,
+        };
         let router = Router::new()
-            .route("/metrics", routing::get(metrics_endpoint))
+            .route(METRICS_ENDPOINT, routing::get(metrics_endpoint))
+            .route(
+                METRICS_STREAM_ENDPOINT,
+                routing::get(metrics_stream_endpoint),
+            )
+            .route(
+                METRICS_SUMMARY_ENDPOINT,
+                routing::get(metrics_summary_endpoint),
+            )
+            .route(
+                METRICS_CUSTOM_ENDPOINT,
+                routing::post(custom_metrics_endpoint),
+            )
+            .route(PROMETHEUS_ENDPOINT, routing::get(prometheus_endpoint))
+            .route(PROCESSES_TOP_ENDPOINT, routing::get(processes_top_endpoint))
+            .route(
+                METRICS_WEBSOCKET_ENDPOINT,
+                routing::get(metrics_websocket_endpoint),
+            )
             .with_state(state);
         let listener = tokio::net::TcpListener::bind(&socket_addr)
             .await
@@ -96,14 +313,437 @@ pub async fn axum_thread(
     }
 }
 
+/// Query parameters of the metrics endpoints.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct MetricsQuery {
+    /// Which tier of the history to return, realtime by default.
+    #[serde(default)]
+    pub resolution: Resolution,
+
+    /// Only return the series needed to render these types, given as a
+    /// comma-separated list such as `cpu,net`.
+    #[serde(
+        default,
+        rename = "type",
+        with = "measurement_type_list",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub measurement_types: Option<Vec<MeasurementType>>,
+
+    /// Only return the newest `samples` measurements of every series.
+    ///
+    /// Defaults to the length of the realtime tier, i.e. `--graph-length`
+    /// times two, so that graphs are equally long at every resolution. This
+    /// means that `?resolution=hour` only returns the newest 10 hour averages
+    /// with the default graph length, ask for `?samples=168` to get the whole
+    /// week (or `?samples=60` for the whole hour of minute averages).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub samples: Option<usize>,
+
+    /// Leave out the idle measurements that series are padded with up to the
+    /// length of a graph, e.g. for statistics that padding would skew.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub recorded: bool,
+}
+
+impl MetricsQuery {
+    /// Collect the metrics that were asked for.
+    pub fn build(&self, resource_monitor: &ResourceMonitor) -> Metrics {
+        let mut metrics = match self.recorded {
+            true => resource_monitor.build_recorded_metrics(self.resolution),
+            false => resource_monitor.build_metrics(self.resolution),
+        };
+        self.select(&mut metrics, resource_monitor.realtime_capacity());
+        metrics
+    }
+
+    /// Drop the series and measurements that weren't asked for, keeping
+    /// `default_samples` measurements unless told otherwise.
+    pub fn select(&self, metrics: &mut Metrics, default_samples: usize) {
+        if let Some(measurement_types) = &self.measurement_types {
+            metrics.retain(measurement_types);
+        }
+        metrics.truncate(self.samples.unwrap_or(default_samples));
+    }
+}
+
+/// Query parameters of the top processes endpoint, e.g. `?by=mem&n=10`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct ProcessesQuery {
+    /// Which resource to rank processes by, CPU by default.
+    #[serde(default)]
+    pub by: ProcessSort,
+
+    /// How many processes to return.
+    #[serde(default = "ProcessesQuery::default_count")]
+    pub n: usize,
+}
+
+impl ProcessesQuery {
+    const fn default_count() -> usize {
+        5
+    }
+}
+
+impl Default for ProcessesQuery {
+    fn default() -> Self {
+        Self {
+            by: ProcessSort::default(),
+            n: Self::default_count(),
+        }
+    }
+}
+
+/// (De)serializes measurement types as a comma-separated list of their names,
+/// since query strings have no notion of sequences.
+mod measurement_type_list {
+    use clap::ValueEnum;
+    use serde::de::Error as _;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::resource_monitor::MeasurementType;
+
+    #[expect(clippy::ref_option)]
+    pub fn serialize<S: Serializer>(
+        measurement_types: &Option<Vec<MeasurementType>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let names = measurement_types
+            .iter()
+            .flatten()
+            .map(|measurement_type| {
+                let value = measurement_type
+                    .to_possible_value()
+                    .ok_or_else(|| S::Error::custom("skipped measurement type"))?;
+                Ok(value.get_name().to_owned())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        serializer.serialize_str(&names.join(","))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<MeasurementType>>, D::Error> {
+        let names = String::deserialize(deserializer)?;
+        names
+            .split(',')
+            .map(|name| {
+                MeasurementType::from_str(name.trim(), false).map_err(|_| {
+                    let known = MeasurementType::value_variants()
+                        .iter()
+                        .filter_map(ValueEnum::to_possible_value)
+                        .map(|value| value.get_name().to_owned())
+                        .collect::<Vec<_>>();
+                    D::Error::custom(format!(
+                        "unknown measurement type `{name}`, expected one of {}",
+                        known.join(", ")
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+}
+
+/// A message sent by WebSocket clients to choose what they want to receive.
+///
+/// For example, `{"subscribe": ["cpu", "net-rx"]}`. Each subscription replaces
+/// the previous one, and an empty list pauses the updates.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Subscription {
+    pub subscribe: Vec<MeasurementType>,
+}
+
+/// A message sent to WebSocket clients whose message could not be understood.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SubscriptionError {
+    pub error: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct AxumState {
     resource_monitor: Arc<AsyncMutex<ResourceMonitor>>,
+    update_notifier: watch::Sender<()>,
 }
 
 #[tracing::instrument(skip_all)]
 #[axum::debug_handler]
-pub async fn metrics_endpoint(State(state): State<AxumState>) -> Json<Metrics> {
-    let metrics = state.resource_monitor.lock().await.build_metrics();
+pub async fn metrics_endpoint(
+    State(state): State<AxumState>,
+    Query(query): Query<MetricsQuery>,
+) -> Json<Metrics> {
+    let metrics = query.build(&*state.resource_monitor.lock().await);
     Json(metrics)
 }
+
+/// Summarize every series, see [`crate::summary::summarize`].
+#[tracing::instrument(skip_all)]
+#[axum::debug_handler]
+pub async fn metrics_summary_endpoint(
+    State(state): State<AxumState>,
+    Query(query): Query<MetricsQuery>,
+) -> Json<Summary> {
+    let query = MetricsQuery {
+        recorded: true,
+        ..query
+    };
+    let metrics = query.build(&*state.resource_monitor.lock().await);
+    Json(crate::summary::summarize(&metrics))
+}
+
+/// Record a measurement of the custom series `name`, which can then be
+/// rendered as `custom:NAME`.
+///
+/// Responds with `400 Bad Request` if the name or value is invalid, or if a
+/// new series comes without thresholds, and with `429 Too Many Requests` if
+/// there are too many custom series already.
+#[tracing::instrument(skip(state))]
+#[axum::debug_handler]
+pub async fn custom_metrics_endpoint(
+    State(state): State<AxumState>,
+    Path(name): Path<String>,
+    Json(measurement): Json<CustomMeasurement>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state
+        .resource_monitor
+        .lock()
+        .await
+        .push_custom(&name, measurement)
+        .map(|()| StatusCode::NO_CONTENT)
+        .map_err(|error| {
+            let status = match error {
+                CustomMeasurementError::TooManySeries => StatusCode::TOO_MANY_REQUESTS,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            (status, error.to_string())
+        })
+}
+
+/// List the processes using the most CPU or memory, see [`ProcessesQuery`].
+///
+/// Responds with `404 Not Found` unless the server was started with
+/// `--track-processes`.
+#[tracing::instrument(skip_all)]
+#[axum::debug_handler]
+pub async fn processes_top_endpoint(
+    State(state): State<AxumState>,
+    Query(query): Query<ProcessesQuery>,
+) -> Result<Json<Vec<ProcessUsage>>, (StatusCode, &'static str)> {
+    state
+        .resource_monitor
+        .lock()
+        .await
+        .top_processes(query.by, query.n)
+        .map(Json)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "Processes are not tracked, start the server with --track-processes",
+        ))
+}
+
+/// Expose the newest measurements for Prometheus to scrape.
+#[tracing::instrument(skip_all)]
+#[axum::debug_handler]
+pub async fn prometheus_endpoint(State(state): State<AxumState>) -> Response {
+    let (metrics, net_totals) = {
+        let resource_monitor = state.resource_monitor.lock().await;
+        (
+            resource_monitor.build_metrics(Resolution::Realtime),
+            resource_monitor.net_totals(),
+        )
+    };
+
+    match crate::prometheus::render(&metrics, &net_totals) {
+        Ok(exposition) => (
+            [(header::CONTENT_TYPE, crate::prometheus::CONTENT_TYPE)],
+            exposition,
+        )
+            .into_response(),
+        Err(error) => {
+            tracing::error!(?error, "Failed to render the Prometheus exposition");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Stream [`Metrics`] as newline-delimited JSON whenever they are refreshed.
+///
+/// The current metrics are sent right away, so that clients don't have to wait
+/// for the next refresh to display something.
+#[tracing::instrument(skip_all)]
+#[axum::debug_handler]
+pub async fn metrics_stream_endpoint(
+    State(state): State<AxumState>,
+    Query(query): Query<MetricsQuery>,
+) -> impl IntoResponse {
+    let mut update_receiver = state.update_notifier.subscribe();
+    update_receiver.mark_changed();
+
+    let resource_monitor = state.resource_monitor;
+    let metrics_stream = futures_util::stream::unfold(update_receiver, move |mut receiver| {
+        let resource_monitor = Arc::clone(&resource_monitor);
+        let query = query.clone();
+        async move {
+            receiver.changed().await.ok()?;
+            let metrics = query.build(&*resource_monitor.lock().await);
+            let line = serde_json::to_vec(&metrics).map(|mut line| {
+                line.push(b'\n');
+                line
+            });
+            Some((line, receiver))
+        }
+    });
+
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(metrics_stream),
+    )
+}
+
+/// Push the newest measurement of every subscribed series over a WebSocket.
+///
+/// Nothing is sent until the client subscribes with a [`Subscription`]. After
+/// that, every refresh of the server produces a [`Metrics`] message with a
+/// single (the newest) measurement of each subscribed series.
+#[tracing::instrument(skip_all)]
+#[axum::debug_handler]
+pub async fn metrics_websocket_endpoint(
+    State(state): State<AxumState>,
+    websocket_upgrade: WebSocketUpgrade,
+) -> Response {
+    websocket_upgrade.on_upgrade(move |socket| metrics_websocket(socket, state))
+}
+
+async fn metrics_websocket(mut socket: WebSocket, state: AxumState) {
+    let mut update_receiver = state.update_notifier.subscribe();
+    let mut subscription = Vec::new();
+
+    loop {
+        let reply = tokio::select! {
+            changed = update_receiver.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+
+                if subscription.is_empty() {
+                    continue;
+                }
+
+                let mut metrics = state
+                    .resource_monitor
+                    .lock()
+                    .await
+                    .build_metrics(Resolution::Realtime);
+                metrics.retain(&subscription);
+                metrics.truncate(1);
+                serde_json::to_string(&metrics)
+            }
+
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<Subscription>(&text) {
+                    Ok(new_subscription) => {
+                        tracing::debug!(?new_subscription, "Subscription changed");
+                        subscription = new_subscription.subscribe;
+                        continue;
+                    }
+                    Err(error) => serde_json::to_string(&SubscriptionError {
+                        error: error.to_string(),
+                    }),
+                },
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            }
+        };
+
+        let Ok(reply) = reply else {
+            break;
+        };
+
+        if socket.send(Message::Text(reply.into())).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::Query;
+    use axum::http::{StatusCode, Uri};
+    use clap::Parser;
+
+    use super::{MetricsQuery, Options, Subscription};
+    use crate::resource_monitor::{MeasurementType, Metrics, Resolution};
+    use crate::units::MemUsage;
+
+    #[test]
+    fn seconds_parsing() {
+        let parse = |option: &str, seconds: &str| {
+            Options::try_parse_from(["sulphur_server", option, seconds])
+                .map(|options| (options.snapshot_seconds, options.custom_idle_seconds))
+        };
+        for option in [
+            "--snapshot-seconds",
+            "--custom-idle-seconds",
+            "--minute-seconds",
+            "--hour-seconds",
+        ] {
+            assert!(parse(option, "90").is_ok());
+            for seconds in ["0", "-1", "NaN", "inf", "soon"] {
+                assert!(parse(option, seconds).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn metrics_query_parsing() {
+        let uri = Uri::from_static("/metrics?type=cpu,net-rx&samples=20");
+        let Query(query) = Query::<MetricsQuery>::try_from_uri(&uri).unwrap();
+        assert_eq!(query.resolution, Resolution::Realtime);
+        assert_eq!(
+            query.measurement_types,
+            Some(vec![MeasurementType::Cpu, MeasurementType::NetRx])
+        );
+        assert_eq!(query.samples, Some(20));
+
+        let uri = Uri::from_static("/metrics?type=cpu,gpu");
+        let rejection = Query::<MetricsQuery>::try_from_uri(&uri).unwrap_err();
+        assert_eq!(rejection.status(), StatusCode::BAD_REQUEST);
+        assert!(
+            rejection
+                .body_text()
+                .contains("unknown measurement type `gpu`")
+        );
+    }
+
+    #[test]
+    fn subscription_parsing() {
+        let subscription: Subscription =
+            serde_json::from_str(r#"{"subscribe": ["cpu", "net-rx"]}"#).unwrap();
+        assert_eq!(
+            subscription.subscribe,
+            [MeasurementType::Cpu, MeasurementType::NetRx]
+        );
+
+        let error =
+            serde_json::from_str::<Subscription>(r#"{"subscribe": ["cpu", "gpu"]}"#).unwrap_err();
+        assert!(error.to_string().contains("gpu"), "{error}");
+    }
+
+    #[test]
+    fn metrics_query_selection() {
+        let selected_samples = |query: &str| {
+            let uri = Uri::try_from(format!("/metrics?{query}")).unwrap();
+            let Query(query) = Query::<MetricsQuery>::try_from_uri(&uri).unwrap();
+            let mut metrics = Metrics {
+                mem_usage: vec![MemUsage::unused(); 60],
+                ..Metrics::default()
+            };
+            query.select(&mut metrics, 10);
+            metrics.mem_usage.len()
+        };
+        assert_eq!(selected_samples("resolution=minute"), 10);
+        assert_eq!(selected_samples("resolution=minute&samples=60"), 60);
+        assert_eq!(selected_samples("samples=4"), 4);
+    }
+}
@@ -0,0 +1,114 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::grading::GradeBand;
+use crate::graph::Graph;
+use crate::resource_monitor::MeasurementSelector;
+
+/// The header of an i3bar protocol stream, followed by the opening of the
+/// endless array of status lines.
+pub const I3BAR_PROTOCOL_HEADER: &str = "{\"version\":1}\n[";
+
+/// How the client presents a rendered [`Graph`].
+#[derive(ValueEnum, Default, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum OutputFormat {
+    /// Just the graph.
+    #[default]
+    Plain,
+    /// JSON for a Waybar custom module (with `"return-type": "json"`).
+    Waybar,
+    /// A JSON block of the i3bar protocol.
+    I3bar,
+}
+
+/// Output of a Waybar custom module.
+///
+/// The CSS class is the [`GradeBand`] of the newest measurement, so the bar can
+/// be styled with e.g. `#custom-sulphur.high { color: red; }`.
+#[derive(Serialize, Clone, Debug)]
+#[must_use]
+pub struct WaybarOutput {
+    pub text: String,
+    pub tooltip: String,
+    pub class: GradeBand,
+    pub percentage: u8,
+}
+
+/// A single block of the i3bar protocol.
+///
+/// The block is marked as urgent when the newest measurement is graded as
+/// [`GradeBand::High`].
+#[derive(Serialize, Clone, Debug)]
+#[must_use]
+pub struct I3barBlock {
+    pub full_text: String,
+    pub name: String,
+    pub urgent: bool,
+}
+
+impl WaybarOutput {
+    #[expect(clippy::cast_possible_truncation)]
+    #[expect(clippy::cast_sign_loss)]
+    pub fn new(graph: &Graph, selector: &MeasurementSelector) -> Self {
+        Self {
+            text: graph.text.clone(),
+            tooltip: format!("{selector}: {}", graph.summary),
+            class: graph.latest_grade.band(),
+            percentage: (graph.latest_grade.level() * 100.).round() as u8,
+        }
+    }
+}
+
+impl I3barBlock {
+    pub fn new(graph: &Graph, selector: &MeasurementSelector) -> Self {
+        Self {
+            full_text: graph.text.clone(),
+            name: format!("sulphur_{selector}"),
+            urgent: graph.latest_grade.band() == GradeBand::High,
+        }
+    }
+}
+
+/// Format a [`Graph`] as a single line (or, for [`OutputFormat::Plain`],
+/// possibly several lines) of output.
+pub fn format_output(
+    graph: &Graph,
+    selector: &MeasurementSelector,
+    output_format: OutputFormat,
+) -> Result<String, serde_json::Error> {
+    match output_format {
+        OutputFormat::Plain => Ok(graph.text.clone()),
+        OutputFormat::Waybar => serde_json::to_string(&WaybarOutput::new(graph, selector)),
+        OutputFormat::I3bar => serde_json::to_string(&I3barBlock::new(graph, selector)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OutputFormat, format_output};
+    use crate::grading::MeasurementGrade;
+    use crate::graph::Graph;
+    use crate::resource_monitor::MeasurementType;
+
+    #[test]
+    fn waybar_output() {
+        let graph = Graph {
+            text: String::from("⣀⣸"),
+            summary: String::from("85% / 43% / 85%"),
+            latest_grade: MeasurementGrade::HIGH,
+        };
+        let output =
+            format_output(&graph, &MeasurementType::Cpu.into(), OutputFormat::Waybar).unwrap();
+        assert_eq!(
+            output,
+            r#"{"text":"⣀⣸","tooltip":"cpu: 85% / 43% / 85%","class":"high","percentage":100}"#
+        );
+
+        let output =
+            format_output(&graph, &MeasurementType::NetRx.into(), OutputFormat::I3bar).unwrap();
+        assert_eq!(
+            output,
+            r#"{"full_text":"⣀⣸","name":"sulphur_net-rx","urgent":true}"#
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Statistics of whole series, for consumers that would rather show numbers
+//! than graphs.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::collector::qualified_series;
+use crate::grading::{GradedMeasurement, percentile_of_sorted, sorted_grading_values};
+use crate::resource_monitor::{DiskMetrics, MeasurementType, Metrics, Series};
+
+/// Statistics of a series over its whole window.
+///
+/// Values are in the same unit as the grading thresholds, e.g. percent for CPU
+/// usage and Mbit/s for network usage.
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, Debug)]
+#[must_use]
+pub struct SeriesSummary {
+    /// The newest measurement.
+    pub latest: f32,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    /// The 95th percentile, i.e. the peak without the occasional spike.
+    pub p95: f32,
+    pub unit: String,
+}
+
+/// A [`SeriesSummary`] of every series, by name.
+///
+/// Collector series keep their names, e.g. `cpu`, `net-rx/eth0`,
+/// `cpu-cores/0` or `command/queue`, and the other measurements are named
+/// after their [`MeasurementType`], e.g. `mem`, qualified by the cgroup for
+/// cgroup usage, e.g. `mem/system.slice`. The busiest core is summarized as
+/// `cpu-max`.
+pub type Summary = BTreeMap<String, SeriesSummary>;
+
+impl SeriesSummary {
+    const PEAK_PERCENTILE: f32 = 95.;
+
+    /// Summarize a newest-first series, or return [`None`] if it is empty.
+    #[expect(clippy::cast_precision_loss)]
+    pub fn new<M: GradedMeasurement>(series: &[M]) -> Option<Self> {
+        let latest = series.first()?.grading_value();
+        let values = sorted_grading_values(series);
+        let mean = match values.len() {
+            0 => 0.,
+            length => values.iter().sum::<f32>() / length as f32,
+        };
+
+        Some(Self {
+            latest,
+            min: values.first().copied().unwrap_or(0.),
+            max: values.last().copied().unwrap_or(0.),
+            mean,
+            p95: percentile_of_sorted(&values, Self::PEAK_PERCENTILE).unwrap_or(0.),
+            unit: M::UNIT.trim().to_owned(),
+        })
+    }
+
+    /// Summarize a collector series, or return [`None`] if it is empty.
+    #[must_use]
+    pub fn of_series(series: &Series) -> Option<Self> {
+        Some(Self {
+            unit: series.unit.trim().to_owned(),
+            ..Self::new(&series.values)?
+        })
+    }
+}
+
+/// Summarize every non-empty series of `metrics`.
+#[must_use]
+pub fn summarize(metrics: &Metrics) -> Summary {
+    let collector_series = metrics
+        .series
+        .iter()
+        .map(|(name, series)| (name.clone(), SeriesSummary::of_series(series)))
+        .chain([(
+            MeasurementType::CpuMax.name(),
+            metrics
+                .max_cpu_core_usage()
+                .as_ref()
+                .and_then(SeriesSummary::of_series),
+        )]);
+    let system_series = disk_summaries(&metrics.disk, None).chain([
+        (
+            MeasurementType::Mem.name(),
+            SeriesSummary::new(&metrics.mem_usage),
+        ),
+        (
+            MeasurementType::Swap.name(),
+            SeriesSummary::new(&metrics.swap_usage),
+        ),
+        (
+            MeasurementType::Temp.name(),
+            SeriesSummary::new(&metrics.temperature),
+        ),
+        (
+            MeasurementType::Load.name(),
+            SeriesSummary::new(&metrics.load_average),
+        ),
+        (
+            MeasurementType::CpuPressure.name(),
+            SeriesSummary::new(&metrics.cpu_pressure),
+        ),
+        (
+            MeasurementType::MemPressure.name(),
+            SeriesSummary::new(&metrics.memory_pressure),
+        ),
+        (
+            MeasurementType::IoPressure.name(),
+            SeriesSummary::new(&metrics.io_pressure),
+        ),
+    ]);
+    let cgroup_series = metrics.cgroups.iter().flat_map(|(cgroup, usage)| {
+        disk_summaries(&usage.disk, Some(cgroup)).chain([
+            (
+                qualified_series(&MeasurementType::Cpu.name(), cgroup),
+                SeriesSummary::new(&usage.cpu_usage),
+            ),
+            (
+                qualified_series(&MeasurementType::Mem.name(), cgroup),
+                SeriesSummary::new(&usage.mem_usage),
+            ),
+        ])
+    });
+
+    collector_series
+        .chain(system_series)
+        .chain(cgroup_series)
+        .filter_map(|(name, summary)| Some((name, summary?)))
+        .collect()
+}
+
+/// Summarize disk throughput, qualified by `cgroup` if it is a cgroup's.
+fn disk_summaries<'a>(
+    disk: &'a DiskMetrics,
+    cgroup: Option<&'a str>,
+) -> impl Iterator<Item = (String, Option<SeriesSummary>)> + 'a {
+    [
+        (MeasurementType::Disk, &disk.disk_io_rate),
+        (MeasurementType::DiskRead, &disk.disk_read_rate),
+        (MeasurementType::DiskWrite, &disk.disk_write_rate),
+    ]
+    .into_iter()
+    .map(move |(measurement_type, series)| {
+        let name = measurement_type.name();
+        let name = cgroup.map_or_else(|| name.clone(), |cgroup| qualified_series(&name, cgroup));
+        (name, SeriesSummary::new(series))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{SeriesSummary, summarize};
+    use crate::resource_monitor::{CgroupMetrics, Metrics, Series};
+    use crate::units::{CpuUsage, MemUsage};
+
+    #[test]
+    fn series_summary() {
+        let series = [30., 10., 50., 20., 40.].map(CpuUsage::from_percentage);
+        let summary = SeriesSummary::new(&series).unwrap();
+        let statistics = [
+            summary.latest,
+            summary.min,
+            summary.max,
+            summary.mean,
+            summary.p95,
+        ];
+        for (statistic, expected) in statistics.into_iter().zip([30., 10., 50., 30., 50.]) {
+            assert!((statistic - expected).abs() <= 10e-6);
+        }
+        assert_eq!(summary.unit, "%");
+
+        assert!(SeriesSummary::new::<CpuUsage>(&[]).is_none());
+    }
+
+    #[test]
+    fn metrics_summary() {
+        let metrics = Metrics {
+            series: BTreeMap::from([
+                (String::from("cpu"), Series::cpu_usage(vec![25.])),
+                (String::from("cpu-cores/0"), Series::cpu_usage(vec![10.])),
+                (String::from("cpu-cores/1"), Series::cpu_usage(vec![40.])),
+                (String::from("command/queue"), Series::cpu_usage(vec![])),
+            ]),
+            mem_usage: vec![MemUsage::from_bytes(1, 4)],
+            cgroups: BTreeMap::from([(
+                String::from("system.slice"),
+                CgroupMetrics {
+                    cpu_usage: vec![CpuUsage::from_percentage(5.)],
+                    ..CgroupMetrics::default()
+                },
+            )]),
+            ..Metrics::default()
+        };
+        let summary = summarize(&metrics);
+        assert_eq!(
+            summary.keys().map(String::as_str).collect::<Vec<_>>(),
+            [
+                "cpu",
+                "cpu-cores/0",
+                "cpu-cores/1",
+                "cpu-max",
+                "cpu/system.slice",
+                "mem"
+            ]
+        );
+        assert_eq!(summary["cpu"].unit, "%");
+        assert!((summary["cpu-max"].latest - 40.).abs() <= 10e-6);
+        assert!((summary["cpu/system.slice"].latest - 5.).abs() <= 10e-6);
+    }
+}
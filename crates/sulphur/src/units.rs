@@ -28,6 +28,74 @@ pub struct NetUsageRate {
     duration: Duration,
 }
 
+/// Accumulating disk I/O measurement.
+///
+/// Stores the amount of data that has been read from and/or written to disks
+/// within an arbitrary time period.
+#[derive(Serialize, Deserialize, Add, Sub, Sum, From, PartialEq, Eq, Deref, Clone, Copy, Debug)]
+#[must_use]
+pub struct DiskIo(si::u64::Information);
+
+/// Disk throughput measurement.
+///
+/// Stores the amount of data that has been read from and/or written to disks
+/// within a known time period.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[must_use]
+pub struct DiskIoRate {
+    disk_io: DiskIo,
+    duration: Duration,
+}
+
+/// Instant memory (or swap) utilization measurement.
+///
+/// Stores both the used and the total amount of memory, since the latter is not
+/// necessarily constant (think of swap files being added or removed).
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[must_use]
+pub struct MemUsage {
+    used: si::u64::Information,
+    total: si::u64::Information,
+}
+
+/// Instant filesystem space utilization measurement.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[must_use]
+pub struct FsUsage {
+    used: si::u64::Information,
+    total: si::u64::Information,
+}
+
+/// Instant temperature measurement of a sensor.
+#[derive(Serialize, Deserialize, PartialEq, Deref, Clone, Copy, Debug)]
+#[must_use]
+pub struct Temperature(si::f32::ThermodynamicTemperature);
+
+/// Load averages over one, five and fifteen minutes, per logical CPU core.
+///
+/// Normalizing by the amount of cores makes loads comparable between
+/// machines: `1.0` means that every core had a runnable task on average.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[must_use]
+pub struct LoadAverage {
+    one: f32,
+    five: f32,
+    fifteen: f32,
+}
+
+/// Instant [pressure stall information][psi] of a resource.
+///
+/// Stores the share of time (averaged over the last ten seconds) in which
+/// `some` tasks, or `full`y all tasks, were stalled waiting for the resource.
+///
+/// [psi]: https://docs.kernel.org/accounting/psi.html
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[must_use]
+pub struct Pressure {
+    some: si::f32::Ratio,
+    full: si::f32::Ratio,
+}
+
 impl CpuUsage {
     pub fn from_percentage(percentage: f32) -> Self {
         Self(si::f32::Ratio::new::<si::ratio::percent>(percentage))
@@ -74,6 +142,160 @@ impl NetUsageRate {
     }
 }
 
+impl DiskIo {
+    pub fn from_bytes(bytes: u64) -> Self {
+        Self(si::u64::Information::new::<si::information::byte>(bytes))
+    }
+}
+
+impl DiskIoRate {
+    pub fn idle() -> Self {
+        Self {
+            disk_io: DiskIo::from_bytes(0),
+            duration: Duration::from_secs(1),
+        }
+    }
+
+    pub const fn from_io_and_duration(disk_io: DiskIo, duration: Duration) -> Self {
+        Self { disk_io, duration }
+    }
+
+    pub const fn disk_io(&self) -> DiskIo {
+        self.disk_io
+    }
+
+    #[must_use]
+    pub const fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    #[expect(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn as_information_rate(&self) -> si::f32::InformationRate {
+        let seconds = self.duration.as_secs_f32();
+        let bytes = self.disk_io.get::<si::information::byte>();
+        let bytes_per_second = (bytes as f32) / seconds;
+        si::f32::InformationRate::new::<si::information_rate::byte_per_second>(bytes_per_second)
+    }
+}
+
+impl MemUsage {
+    pub fn from_bytes(used: u64, total: u64) -> Self {
+        Self {
+            used: si::u64::Information::new::<si::information::byte>(used),
+            total: si::u64::Information::new::<si::information::byte>(total),
+        }
+    }
+
+    pub fn unused() -> Self {
+        Self::from_bytes(0, 0)
+    }
+
+    #[must_use]
+    pub const fn used(&self) -> si::u64::Information {
+        self.used
+    }
+
+    #[must_use]
+    pub const fn total(&self) -> si::u64::Information {
+        self.total
+    }
+
+    /// Express the measurement as a ratio of used memory to total memory.
+    ///
+    /// If there is no memory at all (e.g. a system without swap), the usage is
+    /// considered to be zero.
+    #[must_use]
+    pub fn as_ratio(&self) -> si::f32::Ratio {
+        usage_ratio(self.used, self.total)
+    }
+}
+
+impl FsUsage {
+    pub fn from_bytes(used: u64, total: u64) -> Self {
+        Self {
+            used: si::u64::Information::new::<si::information::byte>(used),
+            total: si::u64::Information::new::<si::information::byte>(total),
+        }
+    }
+
+    #[must_use]
+    pub const fn used(&self) -> si::u64::Information {
+        self.used
+    }
+
+    #[must_use]
+    pub const fn total(&self) -> si::u64::Information {
+        self.total
+    }
+
+    /// Express the measurement as a ratio of used space to total space, zero
+    /// for filesystems without any space (e.g. `/proc`).
+    #[must_use]
+    pub fn as_ratio(&self) -> si::f32::Ratio {
+        usage_ratio(self.used, self.total)
+    }
+}
+
+impl Temperature {
+    pub fn from_celsius(degrees: f32) -> Self {
+        Self(si::f32::ThermodynamicTemperature::new::<
+            si::thermodynamic_temperature::degree_celsius,
+        >(degrees))
+    }
+}
+
+impl LoadAverage {
+    pub const fn per_core(one: f32, five: f32, fifteen: f32) -> Self {
+        Self { one, five, fifteen }
+    }
+
+    #[must_use]
+    pub const fn one(&self) -> f32 {
+        self.one
+    }
+
+    #[must_use]
+    pub const fn five(&self) -> f32 {
+        self.five
+    }
+
+    #[must_use]
+    pub const fn fifteen(&self) -> f32 {
+        self.fifteen
+    }
+}
+
+impl Pressure {
+    pub fn from_percentages(some: f32, full: f32) -> Self {
+        Self {
+            some: si::f32::Ratio::new::<si::ratio::percent>(some),
+            full: si::f32::Ratio::new::<si::ratio::percent>(full),
+        }
+    }
+
+    #[must_use]
+    pub const fn some(&self) -> si::f32::Ratio {
+        self.some
+    }
+
+    #[must_use]
+    pub const fn full(&self) -> si::f32::Ratio {
+        self.full
+    }
+}
+
+#[expect(clippy::cast_precision_loss)]
+fn usage_ratio(used: si::u64::Information, total: si::u64::Information) -> si::f32::Ratio {
+    let used = used.get::<si::information::byte>();
+    let total = total.get::<si::information::byte>();
+    let ratio = match total {
+        0 => 0.,
+        total => (used as f32) / (total as f32),
+    };
+    si::f32::Ratio::new::<si::ratio::ratio>(ratio)
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -81,7 +303,7 @@ mod tests {
     use uom::si;
 
     use super::NetUsage;
-    use crate::units::NetUsageRate;
+    use crate::units::{DiskIo, DiskIoRate, MemUsage, NetUsageRate};
 
     #[test]
     fn net_usage_addition() {
@@ -112,4 +334,26 @@ mod tests {
 
         assert!((bytes_per_second - 102.4).abs() <= 10e-6);
     }
+
+    #[test]
+    fn mem_usage_ratio() {
+        let mem_usage = MemUsage::from_bytes(256, 1024);
+        let percentage = mem_usage.as_ratio().get::<si::ratio::percent>();
+        assert!((percentage - 25.).abs() <= 10e-6);
+
+        let no_swap = MemUsage::unused();
+        assert!(no_swap.as_ratio().get::<si::ratio::percent>().abs() <= 10e-6);
+    }
+
+    #[test]
+    fn disk_io_rate() {
+        let disk_io = DiskIo::from_bytes(3_000_000) + DiskIo::from_bytes(1_000_000);
+        let disk_io_rate = DiskIoRate::from_io_and_duration(disk_io, Duration::from_secs(2));
+
+        let megabytes_per_second = disk_io_rate
+            .as_information_rate()
+            .get::<si::information_rate::megabyte_per_second>();
+
+        assert!((megabytes_per_second - 2.).abs() <= 10e-6);
+    }
 }